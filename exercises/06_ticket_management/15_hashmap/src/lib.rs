@@ -4,6 +4,7 @@
 use std::collections::HashMap;
 use std::ops::{Index, IndexMut};
 use ticket_fields::{TicketDescription, TicketTitle};
+use ticket_repository::TicketRepository;
 
 #[derive(Clone)]
 pub struct TicketStore {
@@ -65,6 +66,38 @@ impl TicketStore {
     }
 }
 
+impl TicketRepository for TicketStore {
+    type Id = TicketId;
+    type Ticket = Ticket;
+    type Draft = TicketDraft;
+    type Iter<'a> = std::collections::hash_map::Values<'a, TicketId, Ticket>;
+
+    fn add_ticket(&mut self, draft: TicketDraft) -> TicketId {
+        TicketStore::add_ticket(self, draft)
+    }
+
+    fn get(&self, id: TicketId) -> Option<&Ticket> {
+        TicketStore::get(self, id)
+    }
+
+    fn get_mut(&mut self, id: TicketId) -> Option<&mut Ticket> {
+        TicketStore::get_mut(self, id)
+    }
+
+    fn remove(&mut self, id: TicketId) -> Option<Ticket> {
+        self.tickets.remove(&id)
+    }
+
+    /// Iterates over the tickets in no particular order.
+    fn iter(&self) -> Self::Iter<'_> {
+        self.tickets.values()
+    }
+
+    fn len(&self) -> usize {
+        self.tickets.len()
+    }
+}
+
 impl Index<TicketId> for TicketStore {
     type Output = Ticket;
 
@@ -118,4 +151,23 @@ mod tests {
         let ticket = &store[id];
         assert_eq!(ticket.status, Status::InProgress);
     }
+
+    mod conformance {
+        use crate::{Ticket, TicketDraft, TicketStore};
+        use ticket_fields::test_helpers::{ticket_description, ticket_title};
+        use ticket_fields::TicketTitle;
+
+        fn draft() -> TicketDraft {
+            TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            }
+        }
+
+        fn edit(ticket: &mut Ticket) {
+            ticket.title = TicketTitle::try_from("Edited").unwrap();
+        }
+
+        ticket_repository::conformance_tests!(TicketStore::new, draft, edit);
+    }
 }
@@ -0,0 +1,166 @@
+//! All-or-nothing bulk operations on a `TicketStore`.
+//!
+//! A batch is undone and redone as a single operation.
+
+use crate::{Status, TicketDraft, TicketId, TicketPatch, TicketStore};
+use std::collections::HashMap;
+
+/// Why a patch was rejected.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("There is no ticket with id {0}")]
+    TicketNotFound(TicketId),
+    #[error("A ticket cannot move from {from} to {to}")]
+    InvalidTransition { from: Status, to: Status },
+}
+
+/// Every patch of a batch that was rejected, by position in the batch.
+///
+/// When a batch is rejected, none of its patches is applied.
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{} patch(es) of the batch were rejected, so none was applied", .failures.len())]
+pub struct BulkError {
+    pub failures: Vec<(usize, PatchError)>,
+}
+
+impl TicketStore {
+    /// Adds every draft, returning the new ids in the same order.
+    pub fn insert_many(&mut self, drafts: Vec<TicketDraft>) -> Vec<TicketId> {
+        self.transaction(|store| {
+            drafts
+                .into_iter()
+                .map(|draft| store.add_ticket(draft))
+                .collect()
+        })
+    }
+
+    /// Applies every patch in order, or none of them if any is rejected.
+    pub fn patch_many(&mut self, patches: Vec<TicketPatch>) -> Result<(), BulkError> {
+        self.reindex_stale();
+        // The status each patched ticket will have when the next patch is applied.
+        let mut statuses: HashMap<TicketId, Status> = HashMap::new();
+        let mut failures = Vec::new();
+        for (position, patch) in patches.iter().enumerate() {
+            let Some(ticket) = self.get(patch.id) else {
+                failures.push((position, PatchError::TicketNotFound(patch.id)));
+                continue;
+            };
+            let from = statuses
+                .entry(patch.id)
+                .or_insert_with(|| ticket.status.clone());
+            match &patch.status {
+                Some(to) if !self.workflow.permits(from, to) => {
+                    failures.push((
+                        position,
+                        PatchError::InvalidTransition {
+                            from: from.clone(),
+                            to: to.clone(),
+                        },
+                    ));
+                }
+                Some(to) => *from = to.clone(),
+                None => {}
+            }
+        }
+        if !failures.is_empty() {
+            return Err(BulkError { failures });
+        }
+        self.transaction(|store| {
+            for patch in patches {
+                store
+                    .patch(patch)
+                    .expect("every patch of the batch was checked");
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Status, Ticket};
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    fn start(id: TicketId) -> TicketPatch {
+        TicketPatch {
+            id,
+            title: None,
+            description: None,
+            status: Some(Status::InProgress),
+        }
+    }
+
+    fn snapshot(store: &TicketStore) -> Vec<Ticket> {
+        store.into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn insert_many_is_undone_as_one() {
+        let mut store = TicketStore::new();
+        let ids = store.insert_many(vec![draft(), draft(), draft()]);
+        assert_eq!(ids.len(), 3);
+        assert!(ids.iter().all(|&id| store.get(id).is_some()));
+
+        assert!(store.undo());
+        assert!(snapshot(&store).is_empty());
+    }
+
+    #[test]
+    fn patch_many_is_all_or_nothing() {
+        let mut store = TicketStore::new();
+        let ids = store.insert_many(vec![draft(), draft()]);
+        let before = snapshot(&store);
+        let missing = TicketId::new(100);
+
+        let err = store
+            .patch_many(vec![start(ids[0]), start(missing), start(ids[1])])
+            .unwrap_err();
+        assert_eq!(err.failures, [(1, PatchError::TicketNotFound(missing))]);
+        assert_eq!(snapshot(&store), before);
+
+        store
+            .patch_many(ids.iter().map(|&id| start(id)).collect())
+            .unwrap();
+        assert_eq!(store.by_status(Status::InProgress).len(), 2);
+        assert!(store.undo());
+        assert_eq!(snapshot(&store), before);
+    }
+
+    #[test]
+    fn patch_many_follows_the_workflow() {
+        let mut store = TicketStore::new();
+        let ids = store.insert_many(vec![draft(), draft()]);
+        let finish = |id| TicketPatch {
+            status: Some(Status::Done),
+            ..start(id)
+        };
+
+        // A batch may move a ticket through several transitions in turn...
+        store
+            .patch_many(vec![start(ids[0]), finish(ids[0])])
+            .unwrap();
+        assert_eq!(store[ids[0]].status, Status::Done);
+
+        // ...but not skip one.
+        let err = store
+            .patch_many(vec![start(ids[0]), finish(ids[1])])
+            .unwrap_err();
+        let invalid = |from, to| PatchError::InvalidTransition { from, to };
+        assert_eq!(
+            err.failures,
+            [
+                (0, invalid(Status::Done, Status::InProgress)),
+                (1, invalid(Status::ToDo, Status::Done)),
+            ]
+        );
+        assert_eq!(store[ids[1]].status, Status::ToDo);
+    }
+}
@@ -0,0 +1,738 @@
+//! A `TicketStore` persisted through an append-only write-ahead log.
+//!
+//! Each log record is framed as
+//! `[length: u32 LE][crc32 of length: u32 LE][crc32 of payload: u32 LE][payload]`,
+//! where the payload is the JSON encoding of a [`Record`]. The length has a
+//! checksum of its own, so that a corrupt length can't pass for a torn record.
+//! Every mutation is appended (and synced) before it is applied in memory,
+//! so a crash can lose at most the mutation that was being written.
+
+use crate::history::Stamp;
+use crate::{
+    LinkError, PatchError, Status, Ticket, TicketDraft, TicketEvent, TicketId, TicketLink,
+    TicketPatch, TicketStore, TransitionError,
+};
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+const HEADER_LEN: usize = 12;
+
+/// A logged mutation, with the time at which it was made and by whom,
+/// so that replaying it records the same event as the original did.
+#[derive(Serialize, Deserialize)]
+enum Record {
+    Insert {
+        ticket: Ticket,
+        stamp: Stamp,
+    },
+    Patch {
+        patch: TicketPatch,
+        stamp: Stamp,
+    },
+    Transition {
+        id: TicketId,
+        to: Status,
+        stamp: Stamp,
+    },
+    Remove {
+        id: TicketId,
+        stamp: Stamp,
+    },
+    Link {
+        link: TicketLink,
+        stamp: Stamp,
+    },
+    Unlink {
+        link: TicketLink,
+        stamp: Stamp,
+    },
+    /// The whole store, written by compaction as the first record of the log.
+    Snapshot {
+        counter: u64,
+        tickets: Vec<Ticket>,
+        links: Vec<TicketLink>,
+        events: Vec<TicketEvent>,
+    },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalError {
+    #[error("I/O error on the ticket log")]
+    Io(#[from] io::Error),
+    #[error("Failed to encode a ticket log record")]
+    Encode(#[from] serde_json::Error),
+    #[error("The record at byte {offset} of the ticket log is corrupt")]
+    Corrupt { offset: u64 },
+    #[error("The ticket log could not be repaired after a failed write. Reopen it")]
+    Poisoned,
+    #[error("The patch was rejected")]
+    Rejected(#[from] PatchError),
+    #[error("The transition was rejected")]
+    Transition(#[from] TransitionError),
+    #[error("The link was rejected")]
+    Link(#[from] LinkError),
+}
+
+/// What happened while replaying the log on open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RecoveryReport {
+    pub records_replayed: usize,
+    /// Bytes of a truncated or corrupt record dropped from the end of the log.
+    pub bytes_discarded: u64,
+}
+
+pub struct DurableTicketStore {
+    store: TicketStore,
+    path: PathBuf,
+    log: File,
+    /// The length of the log up to the end of its last whole record.
+    len: u64,
+    /// Set when the end of the log may hold part of a record, so nothing can be appended.
+    poisoned: bool,
+    records_since_compaction: usize,
+    compaction_threshold: usize,
+    recovery: RecoveryReport,
+}
+
+impl DurableTicketStore {
+    /// The number of appended records after which the log is compacted, by default.
+    pub const DEFAULT_COMPACTION_THRESHOLD: usize = 1000;
+
+    /// Opens the log at `path`, creating it if needed, and replays it.
+    ///
+    /// A truncated or corrupt last record, as left by a crash while writing it,
+    /// is discarded and cut off the file. A corrupt record anywhere else is
+    /// reported as [`WalError::Corrupt`].
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, WalError> {
+        let path = path.as_ref().to_path_buf();
+        let mut log = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create(true)
+            .open(&path)?;
+        let mut bytes = Vec::new();
+        log.read_to_end(&mut bytes)?;
+
+        let mut store = TicketStore::new();
+        let mut recovery = RecoveryReport::default();
+        let mut offset = 0;
+        while offset < bytes.len() {
+            match decode(&bytes[offset..]) {
+                Frame::Whole(record, len) => {
+                    apply(&mut store, record)?;
+                    offset += len;
+                    recovery.records_replayed += 1;
+                }
+                Frame::Torn => break,
+                Frame::Corrupt(Some(len)) if offset + len == bytes.len() => break,
+                Frame::Corrupt(_) => {
+                    return Err(WalError::Corrupt {
+                        offset: offset as u64,
+                    })
+                }
+            }
+        }
+        recovery.bytes_discarded = (bytes.len() - offset) as u64;
+        if recovery.bytes_discarded > 0 {
+            log.set_len(offset as u64)?;
+            log.sync_data()?;
+        }
+
+        Ok(Self {
+            store,
+            path,
+            log,
+            len: offset as u64,
+            poisoned: false,
+            records_since_compaction: recovery.records_replayed,
+            compaction_threshold: Self::DEFAULT_COMPACTION_THRESHOLD,
+            recovery,
+        })
+    }
+
+    /// Compacts the log every `threshold` appended records.
+    pub fn with_compaction_threshold(mut self, threshold: usize) -> Self {
+        self.compaction_threshold = threshold;
+        self
+    }
+
+    pub fn recovery(&self) -> RecoveryReport {
+        self.recovery
+    }
+
+    /// Attributes the changes made from now on to `actor`.
+    pub fn set_actor(&mut self, actor: impl Into<String>) {
+        self.store.set_actor(actor);
+    }
+
+    /// Read-only access to the in-memory state.
+    /// All mutations must go through the log.
+    pub fn store(&self) -> &TicketStore {
+        &self.store
+    }
+
+    pub fn add_ticket(&mut self, draft: TicketDraft) -> Result<TicketId, WalError> {
+        let ticket = Ticket {
+            id: self.store.generate_id(),
+            title: draft.title,
+            description: draft.description,
+            status: Status::ToDo,
+        };
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Insert {
+            ticket: ticket.clone(),
+            stamp: stamp.clone(),
+        })?;
+        let id = ticket.id;
+        self.store.stamped(stamp, |store| store.restore(ticket));
+        self.compact_if_needed()?;
+        Ok(id)
+    }
+
+    /// Logs and applies `patch`. Nothing is logged if the store rejects it.
+    pub fn patch(&mut self, patch: TicketPatch) -> Result<&Ticket, WalError> {
+        self.store.check_patch(&patch)?;
+        let id = patch.id;
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Patch {
+            patch: patch.clone(),
+            stamp: stamp.clone(),
+        })?;
+        self.store
+            .stamped(stamp, |store| store.patch(patch).map(|_| ()))?;
+        self.compact_if_needed()?;
+        Ok(&self.store[id])
+    }
+
+    /// Logs and applies a transition. Nothing is logged if the workflow rejects it.
+    pub fn transition(&mut self, id: TicketId, to: Status) -> Result<&Ticket, WalError> {
+        self.store.check_transition(id, &to)?;
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Transition {
+            id,
+            to: to.clone(),
+            stamp: stamp.clone(),
+        })?;
+        self.store
+            .stamped(stamp, |store| store.transition(id, to).map(|_| ()))?;
+        self.compact_if_needed()?;
+        Ok(&self.store[id])
+    }
+
+    /// Logs and applies the removal of a ticket, along with its links.
+    /// Returns `Ok(None)` if there is no such ticket, in which case nothing is logged.
+    pub fn remove(&mut self, id: TicketId) -> Result<Option<Ticket>, WalError> {
+        if self.store.get(id).is_none() {
+            return Ok(None);
+        }
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Remove {
+            id,
+            stamp: stamp.clone(),
+        })?;
+        let ticket = self.store.stamped(stamp, |store| store.remove(id));
+        self.compact_if_needed()?;
+        Ok(ticket)
+    }
+
+    /// Logs and adds `link`. Returns `Ok(false)`, and logs nothing,
+    /// if the tickets were already linked this way.
+    pub fn link(&mut self, link: TicketLink) -> Result<bool, WalError> {
+        if !self.store.check_link(link)? {
+            return Ok(false);
+        }
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Link {
+            link,
+            stamp: stamp.clone(),
+        })?;
+        self.store.stamped(stamp, |store| store.link(link))?;
+        self.compact_if_needed()?;
+        Ok(true)
+    }
+
+    /// Logs and removes `link`. Returns `Ok(false)`, and logs nothing,
+    /// if the tickets weren't linked this way.
+    pub fn unlink(&mut self, link: TicketLink) -> Result<bool, WalError> {
+        if !self.store.links.contains(link) {
+            return Ok(false);
+        }
+        let stamp = self.store.history.stamp();
+        self.append(&Record::Unlink {
+            link,
+            stamp: stamp.clone(),
+        })?;
+        self.store.stamped(stamp, |store| store.unlink(link));
+        self.compact_if_needed()?;
+        Ok(true)
+    }
+
+    /// Rewrites the log as a single snapshot of the current state.
+    ///
+    /// The snapshot is written to a temporary file which then atomically
+    /// replaces the log, so a crash during compaction leaves the old log intact.
+    pub fn compact(&mut self) -> Result<(), WalError> {
+        if self.poisoned {
+            return Err(WalError::Poisoned);
+        }
+        let snapshot = encode(&Record::Snapshot {
+            counter: self.store.counter,
+            tickets: self.store.into_iter().cloned().collect(),
+            links: self.store.links.all(),
+            events: self.store.events().cloned().collect(),
+        })?;
+        let temporary = self.path.with_extension("compacting");
+        {
+            let mut file = File::create(&temporary)?;
+            file.write_all(&snapshot)?;
+            file.sync_all()?;
+        }
+        fs::rename(&temporary, &self.path)?;
+        // `self.log` is now the replaced file, which nothing should be appended to.
+        self.poisoned = true;
+        sync_parent(&self.path)?;
+        self.log = OpenOptions::new().append(true).open(&self.path)?;
+        self.len = snapshot.len() as u64;
+        self.poisoned = false;
+        self.records_since_compaction = 0;
+        Ok(())
+    }
+
+    fn append(&mut self, record: &Record) -> Result<(), WalError> {
+        if self.poisoned {
+            return Err(WalError::Poisoned);
+        }
+        let frame = encode(record)?;
+        if let Err(error) = self
+            .log
+            .write_all(&frame)
+            .and_then(|()| self.log.sync_data())
+        {
+            // Cut off whatever part of the record was written, so that the next
+            // one doesn't end up after a torn record, which `open` would drop.
+            let repaired = self
+                .log
+                .set_len(self.len)
+                .and_then(|()| self.log.sync_data());
+            self.poisoned = repaired.is_err();
+            return Err(error.into());
+        }
+        self.len += frame.len() as u64;
+        self.records_since_compaction += 1;
+        Ok(())
+    }
+
+    fn compact_if_needed(&mut self) -> Result<(), WalError> {
+        if self.records_since_compaction >= self.compaction_threshold {
+            self.compact()?;
+        }
+        Ok(())
+    }
+}
+
+/// Makes a rename of `path` durable, by syncing the directory it's in.
+fn sync_parent(path: &Path) -> io::Result<()> {
+    // Windows can't open a directory as a file, and doesn't need to.
+    if cfg!(unix) {
+        let parent = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        File::open(parent)?.sync_all()?;
+    }
+    Ok(())
+}
+
+fn encode(record: &Record) -> Result<Vec<u8>, WalError> {
+    let payload = serde_json::to_vec(record)?;
+    let len = (payload.len() as u32).to_le_bytes();
+    let mut frame = Vec::with_capacity(HEADER_LEN + payload.len());
+    frame.extend_from_slice(&len);
+    frame.extend_from_slice(&crc32fast::hash(&len).to_le_bytes());
+    frame.extend_from_slice(&crc32fast::hash(&payload).to_le_bytes());
+    frame.extend_from_slice(&payload);
+    Ok(frame)
+}
+
+/// A record read back from the log.
+enum Frame {
+    /// The record, with its framed length.
+    Whole(Record, usize),
+    /// The log ends in the middle of the record.
+    Torn,
+    /// The record doesn't match its checksums or can't be decoded. It has the framed
+    /// length, unless the length itself is corrupt.
+    Corrupt(Option<usize>),
+}
+
+/// Decodes the record at the start of `bytes`.
+fn decode(bytes: &[u8]) -> Frame {
+    let Some(header) = bytes.get(..HEADER_LEN) else {
+        return Frame::Torn;
+    };
+    let len_checksum = u32::from_le_bytes(header[4..8].try_into().unwrap());
+    if crc32fast::hash(&header[..4]) != len_checksum {
+        return Frame::Corrupt(None);
+    }
+    let len = u32::from_le_bytes(header[..4].try_into().unwrap()) as usize;
+    let checksum = u32::from_le_bytes(header[8..].try_into().unwrap());
+    // The length is intact, so a payload that runs past the end of the log was cut short.
+    let Some(payload) = bytes.get(HEADER_LEN..HEADER_LEN + len) else {
+        return Frame::Torn;
+    };
+    if crc32fast::hash(payload) != checksum {
+        return Frame::Corrupt(Some(HEADER_LEN + len));
+    }
+    match serde_json::from_slice(payload) {
+        Ok(record) => Frame::Whole(record, HEADER_LEN + len),
+        Err(_) => Frame::Corrupt(Some(HEADER_LEN + len)),
+    }
+}
+
+fn apply(store: &mut TicketStore, record: Record) -> Result<(), WalError> {
+    match record {
+        Record::Insert { ticket, stamp } => store.stamped(stamp, |store| store.restore(ticket)),
+        Record::Patch { patch, stamp } => {
+            store.stamped(stamp, |store| store.patch(patch).map(|_| ()))?;
+        }
+        Record::Transition { id, to, stamp } => {
+            store.stamped(stamp, |store| store.transition(id, to).map(|_| ()))?;
+        }
+        Record::Remove { id, stamp } => {
+            store.stamped(stamp, |store| store.remove(id));
+        }
+        Record::Link { link, stamp } => {
+            store.stamped(stamp, |store| store.link(link))?;
+        }
+        Record::Unlink { link, stamp } => {
+            store.stamped(stamp, |store| store.unlink(link));
+        }
+        Record::Snapshot {
+            counter,
+            tickets,
+            links,
+            events,
+        } => {
+            *store = TicketStore::new();
+            for ticket in tickets {
+                store.insert_ticket(ticket);
+            }
+            for link in links {
+                store.links.insert(link);
+            }
+            for event in events {
+                store.history.push(event);
+            }
+            store.counter = store.counter.max(counter);
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Status;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    fn in_progress(id: TicketId) -> TicketPatch {
+        TicketPatch {
+            id,
+            title: None,
+            description: None,
+            status: Some(Status::InProgress),
+        }
+    }
+
+    #[test]
+    fn replays_on_open() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let first = store.add_ticket(draft()).unwrap();
+        let second = store.add_ticket(draft()).unwrap();
+        store.patch(in_progress(second)).unwrap();
+        drop(store);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(
+            store.recovery(),
+            RecoveryReport {
+                records_replayed: 3,
+                bytes_discarded: 0
+            }
+        );
+        assert_eq!(store.store().get(first).unwrap().status, Status::ToDo);
+        assert_eq!(
+            store.store().get(second).unwrap().status,
+            Status::InProgress
+        );
+    }
+
+    #[test]
+    fn replays_the_actor() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        store.set_actor("alice");
+        let id = store.add_ticket(draft()).unwrap();
+        store.set_actor("bob");
+        store.patch(in_progress(id)).unwrap();
+        drop(store);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        let actors: Vec<Option<&str>> = store
+            .store()
+            .history(id)
+            .map(|e| e.actor.as_deref())
+            .collect();
+        assert_eq!(actors, [Some("alice"), Some("bob")]);
+    }
+
+    #[test]
+    fn rejected_patches_are_not_logged() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let id = store.add_ticket(draft()).unwrap();
+        store.compact().unwrap();
+        let missing = TicketId::new(id.number() + 100);
+        assert!(matches!(
+            store.patch(in_progress(missing)),
+            Err(WalError::Rejected(PatchError::TicketNotFound(_)))
+        ));
+        let done = TicketPatch {
+            status: Some(Status::Done),
+            ..in_progress(id)
+        };
+        assert!(matches!(
+            store.patch(done),
+            Err(WalError::Rejected(PatchError::InvalidTransition { .. }))
+        ));
+        drop(store);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.recovery().records_replayed, 1);
+    }
+
+    #[test]
+    fn discards_truncated_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let kept = store.add_ticket(draft()).unwrap();
+        let len_before_second = fs::metadata(&path).unwrap().len();
+        let lost = store.add_ticket(draft()).unwrap();
+        drop(store);
+
+        // Simulate a crash in the middle of writing the second record.
+        let file = OpenOptions::new().write(true).open(&path).unwrap();
+        file.set_len(len_before_second + 5).unwrap();
+        drop(file);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.recovery().records_replayed, 1);
+        assert_eq!(store.recovery().bytes_discarded, 5);
+        assert!(store.store().get(kept).is_some());
+        assert!(store.store().get(lost).is_none());
+        assert_eq!(fs::metadata(&path).unwrap().len(), len_before_second);
+    }
+
+    #[test]
+    fn discards_corrupt_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        store.add_ticket(draft()).unwrap();
+        let len_before_second = fs::metadata(&path).unwrap().len() as usize;
+        store.add_ticket(draft()).unwrap();
+        drop(store);
+
+        let mut bytes = fs::read(&path).unwrap();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.recovery().records_replayed, 1);
+        assert_eq!(
+            store.recovery().bytes_discarded as usize,
+            bytes.len() - len_before_second
+        );
+
+        // The log stays usable after recovery.
+        let id = store.add_ticket(draft()).unwrap();
+        drop(store);
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert!(store.store().get(id).is_some());
+        assert_eq!(store.recovery().bytes_discarded, 0);
+    }
+
+    #[test]
+    fn replays_every_mutation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let ids: Vec<TicketId> = (0..3).map(|_| store.add_ticket(draft()).unwrap()).collect();
+        store.transition(ids[0], Status::InProgress).unwrap();
+        let parent = TicketLink::Parent {
+            child: ids[1],
+            parent: ids[0],
+        };
+        let blocks = TicketLink::Blocks {
+            blocker: ids[0],
+            blocked: ids[2],
+        };
+        assert!(store.link(parent).unwrap());
+        assert!(store.link(blocks).unwrap());
+        assert!(store.unlink(blocks).unwrap());
+        store.remove(ids[2]).unwrap().unwrap();
+        // Rejected and no-op mutations aren't logged.
+        assert!(store.transition(ids[1], Status::Done).is_err());
+        assert!(!store.unlink(blocks).unwrap());
+        assert!(store.remove(ids[2]).unwrap().is_none());
+        let events: Vec<TicketEvent> = store.store().events().cloned().collect();
+        drop(store);
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.recovery().records_replayed, 8);
+        assert!(store.store().events().eq(&events));
+        assert_eq!(store.store()[ids[0]].status, Status::InProgress);
+        assert_eq!(store.store().parent(ids[1]), Some(ids[0]));
+        assert!(store.store().get(ids[2]).is_none());
+
+        // Links survive compaction too.
+        store.compact().unwrap();
+        drop(store);
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.store().parent(ids[1]), Some(ids[0]));
+    }
+
+    #[test]
+    fn reports_corruption_in_the_middle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        store.add_ticket(draft()).unwrap();
+        store.add_ticket(draft()).unwrap();
+        drop(store);
+
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[HEADER_LEN] ^= 0xff;
+        fs::write(&path, &bytes).unwrap();
+
+        // The records after the corrupt one are valid, so it isn't a torn tail:
+        // dropping it would drop them as well.
+        assert!(matches!(
+            DurableTicketStore::open(&path),
+            Err(WalError::Corrupt { offset: 0 })
+        ));
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, bytes.len());
+    }
+
+    #[test]
+    fn reports_a_corrupt_length_in_the_middle() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        store.add_ticket(draft()).unwrap();
+        store.add_ticket(draft()).unwrap();
+        drop(store);
+
+        // The first record now claims to run past the end of the log.
+        let mut bytes = fs::read(&path).unwrap();
+        bytes[3] ^= 0x7f;
+        fs::write(&path, &bytes).unwrap();
+
+        assert!(matches!(
+            DurableTicketStore::open(&path),
+            Err(WalError::Corrupt { offset: 0 })
+        ));
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, bytes.len());
+    }
+
+    #[test]
+    fn a_failed_append_is_not_applied() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let id = store.add_ticket(draft()).unwrap();
+        // A handle that can't write, nor cut off what it failed to write.
+        store.log = File::open(&path).unwrap();
+        assert!(matches!(
+            store.transition(id, Status::InProgress),
+            Err(WalError::Io(_))
+        ));
+        assert_eq!(store.store()[id].status, Status::ToDo);
+        assert!(matches!(store.add_ticket(draft()), Err(WalError::Poisoned)));
+        drop(store);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert_eq!(store.recovery().records_replayed, 1);
+    }
+
+    #[test]
+    fn history_survives_reopening() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        let id = store.add_ticket(draft()).unwrap();
+        store.patch(in_progress(id)).unwrap();
+        let events: Vec<TicketEvent> = store.store().events().cloned().collect();
+        assert_eq!(events.len(), 2);
+        drop(store);
+
+        // Replaying the log records the events with their original timestamps...
+        let mut store = DurableTicketStore::open(&path).unwrap();
+        assert!(store.store().events().eq(&events));
+
+        // ...and so does restoring a snapshot.
+        store.compact().unwrap();
+        drop(store);
+        let store = DurableTicketStore::open(&path).unwrap();
+        assert!(store.store().events().eq(&events));
+    }
+
+    #[test]
+    fn compaction_preserves_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tickets.log");
+
+        let mut store = DurableTicketStore::open(&path)
+            .unwrap()
+            .with_compaction_threshold(4);
+        let ids: Vec<TicketId> = (0..3).map(|_| store.add_ticket(draft()).unwrap()).collect();
+        for &id in &ids {
+            store.patch(in_progress(id)).unwrap();
+        }
+        let expected = store.store().clone();
+        drop(store);
+
+        let store = DurableTicketStore::open(&path).unwrap();
+        // One snapshot record and the two patches appended after it.
+        assert_eq!(store.recovery().records_replayed, 3);
+        let tickets: Vec<&Ticket> = store.store().into_iter().collect();
+        let expected: Vec<&Ticket> = expected.into_iter().collect();
+        assert_eq!(tickets, expected);
+        assert_eq!(store.store().counter, ids[2].number() + 1);
+    }
+}
@@ -0,0 +1,526 @@
+//! The audit trail of a `TicketStore`.
+//!
+//! Every mutation made through `add_ticket`, `patch`, `transition` and `remove`
+//! is recorded as a [`TicketEvent`], along with who made it and when.
+
+use crate::{Status, Ticket, TicketId, TicketLink, TicketPatch, TicketStore, Workflow};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::SystemTime;
+use ticket_fields::{TicketDescription, TicketTitle};
+
+/// The source of event timestamps.
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+}
+
+/// The system's wall clock.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock stopped at the given time.
+struct StoppedClock(SystemTime);
+
+impl Clock for StoppedClock {
+    fn now(&self) -> SystemTime {
+        self.0
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketEvent {
+    pub ticket_id: TicketId,
+    pub at: SystemTime,
+    /// Who made the change, as set by [`TicketStore::set_actor`].
+    pub actor: Option<String>,
+    pub kind: TicketEventKind,
+}
+
+/// When a change was made, and by whom.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct Stamp {
+    pub(crate) at: SystemTime,
+    pub(crate) actor: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TicketEventKind {
+    /// The ticket was added to the store, in this state.
+    Created(Ticket),
+    /// The fields that a patch changed. Fields set to their current value are left out.
+    Patched(Vec<FieldChange>),
+    Transitioned {
+        from: Status,
+        to: Status,
+    },
+    Removed,
+    Linked(TicketLink),
+    Unlinked(TicketLink),
+}
+
+/// A field of a ticket, with its values before and after a patch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FieldChange {
+    Title {
+        from: TicketTitle,
+        to: TicketTitle,
+    },
+    Description {
+        from: TicketDescription,
+        to: TicketDescription,
+    },
+    Status {
+        from: Status,
+        to: Status,
+    },
+}
+
+impl FieldChange {
+    /// The changes made by `after` to a ticket whose previous values are in `before`.
+    pub(crate) fn between(before: &TicketPatch, after: &TicketPatch) -> Vec<FieldChange> {
+        fn changed<T: Clone + PartialEq>(from: &Option<T>, to: &Option<T>) -> Option<(T, T)> {
+            match (from, to) {
+                (Some(from), Some(to)) if from != to => Some((from.clone(), to.clone())),
+                _ => None,
+            }
+        }
+        let title =
+            changed(&before.title, &after.title).map(|(from, to)| FieldChange::Title { from, to });
+        let description = changed(&before.description, &after.description)
+            .map(|(from, to)| FieldChange::Description { from, to });
+        let status = changed(&before.status, &after.status)
+            .map(|(from, to)| FieldChange::Status { from, to });
+        [title, description, status].into_iter().flatten().collect()
+    }
+
+    /// The patch that makes `changes` to the ticket with the given id.
+    fn patch(id: TicketId, changes: &[FieldChange]) -> TicketPatch {
+        let mut patch = TicketPatch {
+            id,
+            title: None,
+            description: None,
+            status: None,
+        };
+        for change in changes {
+            match change {
+                FieldChange::Title { to, .. } => patch.title = Some(to.clone()),
+                FieldChange::Description { to, .. } => patch.description = Some(to.clone()),
+                FieldChange::Status { to, .. } => patch.status = Some(to.clone()),
+            }
+        }
+        patch
+    }
+}
+
+/// An event that doesn't fit the workflow given to [`TicketStore::replay`].
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum ReplayError {
+    #[error("Event {position} creates a ticket in {status}, which is not a state of the workflow")]
+    UndeclaredStatus { position: usize, status: Status },
+    #[error(
+        "Event {position} moves a ticket from {from} to {to}, which the workflow doesn't allow"
+    )]
+    InvalidTransition {
+        position: usize,
+        from: Status,
+        to: Status,
+    },
+}
+
+#[derive(Clone)]
+pub(crate) struct History {
+    /// The most recent events, oldest first.
+    events: VecDeque<TicketEvent>,
+    /// How many events were dropped from the front of `events`.
+    dropped: usize,
+    /// The positions of each ticket's events, counting the dropped ones.
+    ///
+    /// A ticket stays here after all its events are dropped, for `contains`.
+    by_ticket: HashMap<TicketId, VecDeque<usize>>,
+    limit: Option<usize>,
+    clock: Arc<dyn Clock>,
+    actor: Option<String>,
+}
+
+impl Default for History {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::new(),
+            dropped: 0,
+            by_ticket: HashMap::new(),
+            limit: None,
+            clock: Arc::new(SystemClock),
+            actor: None,
+        }
+    }
+}
+
+impl History {
+    pub(crate) fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Arc::new(clock);
+    }
+
+    /// The stamp of a change made now.
+    pub(crate) fn stamp(&self) -> Stamp {
+        Stamp {
+            at: self.clock.now(),
+            actor: self.actor.clone(),
+        }
+    }
+
+    pub(crate) fn record(&mut self, ticket_id: TicketId, kind: TicketEventKind) {
+        let Stamp { at, actor } = self.stamp();
+        self.push(TicketEvent {
+            ticket_id,
+            at,
+            actor,
+            kind,
+        });
+    }
+
+    /// Whether any event was ever recorded for the ticket with the given id.
+    pub(crate) fn contains(&self, ticket_id: TicketId) -> bool {
+        self.by_ticket.contains_key(&ticket_id)
+    }
+
+    pub(crate) fn push(&mut self, event: TicketEvent) {
+        self.by_ticket
+            .entry(event.ticket_id)
+            .or_default()
+            .push_back(self.dropped + self.events.len());
+        self.events.push_back(event);
+        while self.limit.is_some_and(|limit| self.events.len() > limit) {
+            let Some(oldest) = self.events.pop_front() else {
+                break;
+            };
+            // The oldest event is also the oldest of its ticket.
+            if let Some(positions) = self.by_ticket.get_mut(&oldest.ticket_id) {
+                positions.pop_front();
+            }
+            self.dropped += 1;
+        }
+    }
+}
+
+impl TicketStore {
+    /// Keeps at most `limit` events. Older ones are dropped.
+    ///
+    /// The history is uncapped by default.
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history.limit = Some(limit);
+        self
+    }
+
+    /// Attributes the changes made from now on to `actor`.
+    pub fn set_actor(&mut self, actor: impl Into<String>) {
+        self.history.actor = Some(actor.into());
+    }
+
+    /// Who the changes made from now on are attributed to.
+    pub fn actor(&self) -> Option<&str> {
+        self.history.actor.as_deref()
+    }
+
+    /// Runs `f`, stamping the events it records with `stamp` instead of the
+    /// clock's time and the current actor.
+    pub(crate) fn stamped<T>(&mut self, stamp: Stamp, f: impl FnOnce(&mut Self) -> T) -> T {
+        let clock = std::mem::replace(&mut self.history.clock, Arc::new(StoppedClock(stamp.at)));
+        let actor = std::mem::replace(&mut self.history.actor, stamp.actor);
+        let output = f(self);
+        self.history.clock = clock;
+        self.history.actor = actor;
+        output
+    }
+
+    /// The recorded events, oldest first.
+    pub fn events(&self) -> impl Iterator<Item = &TicketEvent> {
+        self.history.events.iter()
+    }
+
+    /// The events of the ticket with the given id, oldest first.
+    ///
+    /// The history outlives the ticket: it is still available after `remove`.
+    pub fn history(&self, id: TicketId) -> impl Iterator<Item = &TicketEvent> {
+        self.history
+            .by_ticket
+            .get(&id)
+            .into_iter()
+            .flatten()
+            .map(|&position| &self.history.events[position - self.history.dropped])
+    }
+
+    /// Rebuilds a store that follows `workflow` by applying `events` in order.
+    ///
+    /// A status change must follow a transition of `workflow`, either way round:
+    /// undoing a change is recorded as the reverse change.
+    /// The rebuilt store records the same events, with their original timestamps.
+    ///
+    /// Only a whole history can be replayed: one cut short by `with_history_limit`
+    /// misses the tickets created before its first event.
+    pub fn replay<'a>(
+        workflow: Workflow,
+        events: impl IntoIterator<Item = &'a TicketEvent>,
+    ) -> Result<Self, ReplayError> {
+        let mut store = TicketStore::new().with_workflow(workflow);
+        for (position, event) in events.into_iter().enumerate() {
+            store.check_event(position, event)?;
+            match &event.kind {
+                TicketEventKind::Created(ticket) => store.insert_ticket(ticket.clone()),
+                TicketEventKind::Patched(changes) => {
+                    store.apply_patch(FieldChange::patch(event.ticket_id, changes));
+                }
+                TicketEventKind::Transitioned { to, .. } => {
+                    store.apply_patch(TicketPatch {
+                        id: event.ticket_id,
+                        title: None,
+                        description: None,
+                        status: Some(to.clone()),
+                    });
+                }
+                TicketEventKind::Removed => {
+                    store.remove_ticket(event.ticket_id);
+                }
+                TicketEventKind::Linked(link) => {
+                    store.links.insert(*link);
+                }
+                TicketEventKind::Unlinked(link) => {
+                    store.links.remove(*link);
+                }
+            }
+            store.history.push(event.clone());
+        }
+        Ok(store)
+    }
+
+    fn check_event(&self, position: usize, event: &TicketEvent) -> Result<(), ReplayError> {
+        let to = match &event.kind {
+            TicketEventKind::Created(ticket) if !self.workflow.declares(&ticket.status) => {
+                return Err(ReplayError::UndeclaredStatus {
+                    position,
+                    status: ticket.status.clone(),
+                });
+            }
+            TicketEventKind::Patched(changes) => {
+                let status = changes.iter().find_map(|change| match change {
+                    FieldChange::Status { to, .. } => Some(to),
+                    _ => None,
+                });
+                match status {
+                    Some(to) => to,
+                    None => return Ok(()),
+                }
+            }
+            TicketEventKind::Transitioned { to, .. } => to,
+            _ => return Ok(()),
+        };
+        let Some(ticket) = self.get(event.ticket_id) else {
+            return Ok(());
+        };
+        let from = &ticket.status;
+        if self.workflow.permits(from, to) || self.workflow.allows(to, from) {
+            return Ok(());
+        }
+        Err(ReplayError::InvalidTransition {
+            position,
+            from: from.clone(),
+            to: to.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TicketDraft;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::time::Duration;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+    use ticket_fields::TicketTitle;
+
+    /// A clock that moves one second forward every time it's read.
+    #[derive(Default)]
+    struct TickingClock(AtomicU64);
+
+    impl Clock for TickingClock {
+        fn now(&self) -> SystemTime {
+            let seconds = self.0.fetch_add(1, Ordering::SeqCst);
+            SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+        }
+    }
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    fn retitle(id: TicketId, title: &str) -> TicketPatch {
+        TicketPatch {
+            id,
+            title: Some(TicketTitle::try_from(title).unwrap()),
+            description: None,
+            status: None,
+        }
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn records_history() {
+        let mut store = TicketStore::new().with_clock(TickingClock::default());
+        let id = store.add_ticket(draft());
+        let other = store.add_ticket(draft());
+        let title = store[id].title.clone();
+        store.patch(retitle(id, "Renamed")).unwrap();
+        store.transition(id, Status::InProgress).unwrap();
+        // Rejected transitions and patches of missing tickets aren't recorded.
+        store.transition(id, Status::ToDo).unwrap_err();
+        store
+            .patch(retitle(TicketId::new(1000), "Missing"))
+            .unwrap_err();
+        store.remove(id);
+
+        let history: Vec<(SystemTime, &TicketEventKind)> =
+            store.history(id).map(|e| (e.at, &e.kind)).collect();
+        assert_eq!(history.len(), 4);
+        assert!(matches!(history[0], (t, TicketEventKind::Created(_)) if t == at(0)));
+        let renamed = FieldChange::Title {
+            from: title,
+            to: TicketTitle::try_from("Renamed").unwrap(),
+        };
+        assert_eq!(
+            history[1],
+            (at(2), &TicketEventKind::Patched(vec![renamed]))
+        );
+        assert_eq!(
+            history[2],
+            (
+                at(3),
+                &TicketEventKind::Transitioned {
+                    from: Status::ToDo,
+                    to: Status::InProgress
+                }
+            )
+        );
+        assert_eq!(history[3], (at(4), &TicketEventKind::Removed));
+        assert_eq!(store.history(other).count(), 1);
+        assert_eq!(store.events().count(), 5);
+    }
+
+    #[test]
+    fn patches_record_only_what_changed() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        let title = store[id].title.clone();
+        store
+            .patch(TicketPatch {
+                description: Some(store[id].description.clone()),
+                ..retitle(id, "Renamed")
+            })
+            .unwrap();
+
+        let last = store.history(id).last().unwrap();
+        let renamed = FieldChange::Title {
+            from: title,
+            to: TicketTitle::try_from("Renamed").unwrap(),
+        };
+        assert_eq!(last.kind, TicketEventKind::Patched(vec![renamed]));
+    }
+
+    #[test]
+    fn records_the_actor() {
+        let mut store = TicketStore::new();
+        let anonymous = store.add_ticket(draft());
+        store.set_actor("alice");
+        let id = store.add_ticket(draft());
+        store.patch(retitle(id, "Renamed")).unwrap();
+        store.set_actor("bob");
+        store.transition(id, Status::InProgress).unwrap();
+
+        assert_eq!(store.actor(), Some("bob"));
+        assert_eq!(store.history(anonymous).next().unwrap().actor, None);
+        let actors: Vec<Option<&str>> = store.history(id).map(|e| e.actor.as_deref()).collect();
+        assert_eq!(actors, [Some("alice"), Some("alice"), Some("bob")]);
+    }
+
+    #[test]
+    fn history_is_uncapped_by_default() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        for i in 0..20_000 {
+            store.patch(retitle(id, &format!("Title {i}"))).unwrap();
+        }
+
+        assert_eq!(store.history(id).count(), 20_001);
+    }
+
+    #[test]
+    fn history_is_capped() {
+        let mut store = TicketStore::new().with_history_limit(3);
+        let first = store.add_ticket(draft());
+        let second = store.add_ticket(draft());
+        for title in ["One", "Two", "Three"] {
+            store.patch(retitle(second, title)).unwrap();
+        }
+
+        assert_eq!(store.events().count(), 3);
+        assert_eq!(store.history(first).count(), 0);
+        let history: Vec<&TicketEventKind> = store.history(second).map(|e| &e.kind).collect();
+        assert_eq!(history.len(), 3);
+        assert!(history
+            .iter()
+            .all(|kind| matches!(kind, TicketEventKind::Patched(_))));
+    }
+
+    #[test]
+    fn replay_equals_live_state() {
+        let mut store = TicketStore::new();
+        let ids: Vec<TicketId> = (0..5).map(|_| store.add_ticket(draft())).collect();
+        store.patch(retitle(ids[0], "First")).unwrap();
+        store.transition(ids[1], Status::InProgress).unwrap();
+        store.transition(ids[1], Status::Done).unwrap();
+        store.remove(ids[2]);
+        store.transition(ids[3], Status::InProgress).unwrap();
+        store.patch(retitle(ids[3], "Fourth")).unwrap();
+
+        let replayed = TicketStore::replay(Workflow::default(), store.events()).unwrap();
+        let live: Vec<&Ticket> = (&store).into_iter().collect();
+        let rebuilt: Vec<&Ticket> = (&replayed).into_iter().collect();
+        assert_eq!(live, rebuilt);
+        assert!(replayed.events().eq(store.events()));
+        assert_eq!(
+            replayed.by_status(Status::InProgress),
+            store.by_status(Status::InProgress)
+        );
+        assert_eq!(replayed.search("fourth"), store.search("fourth"));
+    }
+
+    #[test]
+    fn replay_follows_the_workflow() {
+        let workflow = Workflow::default().allow(Status::ToDo, Status::Done);
+        let mut store = TicketStore::new().with_workflow(workflow.clone());
+        let id = store.add_ticket(draft());
+        store.transition(id, Status::Done).unwrap();
+        store.undo();
+        assert!(TicketStore::replay(workflow, store.events()).is_ok());
+
+        assert_eq!(
+            TicketStore::replay(Workflow::default(), store.events()).err(),
+            Some(ReplayError::InvalidTransition {
+                position: 1,
+                from: Status::ToDo,
+                to: Status::Done
+            })
+        );
+    }
+}
@@ -0,0 +1,302 @@
+//! How a `TicketStore` picks the ids of new tickets.
+//!
+//! An id is a number, optionally prefixed by a project key: `42` or `CORE-42`.
+//! Ids round-trip through their string form, which is also how they're serialized.
+
+use crate::{TicketId, TicketStore};
+use rand::Rng;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+/// The strategy a `TicketStore` uses to generate ticket ids.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum IdStrategy {
+    /// `0`, `1`, `2`, ...
+    #[default]
+    Sequential,
+    /// `CORE-1`, `CORE-2`, ...
+    Project(ProjectKey),
+    /// Unpredictable numbers, e.g. for ids that are exposed publicly.
+    Random,
+}
+
+/// The prefix of project-scoped ids: 1 to 10 ASCII uppercase letters or digits,
+/// starting with a letter.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ProjectKey {
+    /// Padded with zeroes, so that keys are ordered alphabetically.
+    bytes: [u8; ProjectKey::MAX_LEN],
+    len: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{invalid_key}` is not a valid project key. Use 1 to 10 uppercase letters or digits, starting with a letter")]
+pub struct ParseProjectKeyError {
+    invalid_key: String,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{invalid_id}` is not a valid ticket id. Use a number, optionally prefixed by a project key, e.g. `CORE-42`")]
+pub struct ParseTicketIdError {
+    invalid_id: String,
+}
+
+impl ProjectKey {
+    pub const MAX_LEN: usize = 10;
+
+    pub fn as_str(&self) -> &str {
+        // Only ASCII characters are ever stored.
+        std::str::from_utf8(&self.bytes[..self.len as usize]).unwrap()
+    }
+}
+
+impl FromStr for ProjectKey {
+    type Err = ParseProjectKeyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let valid = s.len() <= Self::MAX_LEN
+            && s.starts_with(|c: char| c.is_ascii_uppercase())
+            && s.bytes()
+                .all(|b| b.is_ascii_uppercase() || b.is_ascii_digit());
+        if !valid {
+            return Err(ParseProjectKeyError {
+                invalid_key: s.to_string(),
+            });
+        }
+        let mut bytes = [0; Self::MAX_LEN];
+        bytes[..s.len()].copy_from_slice(s.as_bytes());
+        Ok(ProjectKey {
+            bytes,
+            len: s.len() as u8,
+        })
+    }
+}
+
+impl TryFrom<&str> for ProjectKey {
+    type Error = ParseProjectKeyError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for ProjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl fmt::Debug for ProjectKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProjectKey({:?})", self.as_str())
+    }
+}
+
+impl TicketId {
+    pub(crate) fn new(number: u64) -> Self {
+        TicketId {
+            project: None,
+            number,
+        }
+    }
+
+    pub fn project(&self) -> Option<ProjectKey> {
+        self.project
+    }
+
+    pub fn number(&self) -> u64 {
+        self.number
+    }
+}
+
+impl fmt::Display for TicketId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.project {
+            Some(project) => write!(f, "{project}-{}", self.number),
+            None => write!(f, "{}", self.number),
+        }
+    }
+}
+
+impl FromStr for TicketId {
+    type Err = ParseTicketIdError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let error = || ParseTicketIdError {
+            invalid_id: s.to_string(),
+        };
+        let (project, number) = match s.rsplit_once('-') {
+            Some((project, number)) => (Some(project.parse().map_err(|_| error())?), number),
+            None => (None, s),
+        };
+        // `u64::from_str` accepts a leading `+`, which would break the round-trip.
+        if !number.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(error());
+        }
+        let number = number.parse().map_err(|_| error())?;
+        Ok(TicketId { project, number })
+    }
+}
+
+impl Serialize for TicketId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for TicketId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        /// Ids used to be serialized as plain numbers.
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Number(u64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Number(number) => Ok(TicketId::new(number)),
+            Repr::Text(text) => text.parse().map_err(serde::de::Error::custom),
+        }
+    }
+}
+
+impl TicketStore {
+    /// Generates the ids of new tickets with `strategy`.
+    pub fn with_id_strategy(mut self, strategy: IdStrategy) -> Self {
+        self.id_strategy = strategy;
+        self
+    }
+
+    pub fn id_strategy(&self) -> IdStrategy {
+        self.id_strategy
+    }
+
+    /// A new id, which no ticket of this store has ever had, not even a removed one.
+    pub(crate) fn generate_id(&mut self) -> TicketId {
+        loop {
+            let id = match self.id_strategy {
+                IdStrategy::Sequential => TicketId::new(self.counter),
+                IdStrategy::Project(project) => TicketId {
+                    project: Some(project),
+                    number: self.counter.max(1),
+                },
+                IdStrategy::Random => TicketId::new(random_number()),
+            };
+            let taken = self.tickets.contains_key(&id)
+                || self.random_ids.contains(&id)
+                || self.history.contains(id);
+            self.reserve(id);
+            if !taken {
+                return id;
+            }
+        }
+    }
+
+    /// Makes sure the current strategy never generates `id`.
+    pub(crate) fn reserve(&mut self, id: TicketId) {
+        match self.id_strategy {
+            // Random ids are spread over the whole range, so the counter can't cover them.
+            IdStrategy::Random => {
+                self.random_ids.insert(id);
+            }
+            IdStrategy::Sequential | IdStrategy::Project(_) => {
+                self.counter = self.counter.max(id.number.saturating_add(1));
+            }
+        }
+    }
+}
+
+/// A random number that can be represented exactly as a JSON number by any client.
+fn random_number() -> u64 {
+    rand::thread_rng().gen_range(0..1 << 53)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TicketDraft;
+    use std::collections::HashSet;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    #[test]
+    fn sequential_ids_are_dense() {
+        let mut store = TicketStore::new();
+        let ids: Vec<u64> = (0..4).map(|_| store.add_ticket(draft()).number()).collect();
+        assert_eq!(ids, [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn project_keys() {
+        let core: ProjectKey = "CORE".parse().unwrap();
+        let mut store = TicketStore::new().with_id_strategy(IdStrategy::Project(core));
+        let first = store.add_ticket(draft());
+        let second = store.add_ticket(draft());
+
+        assert_eq!(first.to_string(), "CORE-1");
+        assert_eq!(second.to_string(), "CORE-2");
+        assert_eq!(first.project(), Some(core));
+        assert_eq!("CORE-2".parse(), Ok(second));
+    }
+
+    #[test]
+    fn random_ids_are_unique() {
+        let mut store = TicketStore::new().with_id_strategy(IdStrategy::Random);
+        let ids: HashSet<TicketId> = (0..100).map(|_| store.add_ticket(draft())).collect();
+        assert_eq!(ids.len(), 100);
+        // Random ids leave the sequential counter alone.
+        assert_eq!(store.counter, 0);
+        assert_eq!(store.random_ids.len(), 100);
+    }
+
+    #[test]
+    fn removed_ids_are_never_reused() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        store.remove(id);
+        assert_ne!(store.add_ticket(draft()), id);
+
+        // Restored tickets reserve their id.
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        let mut ticket = store[id].clone();
+        ticket.id = TicketId::new(5);
+        store.restore(ticket);
+        assert_eq!(store.add_ticket(draft()), TicketId::new(6));
+    }
+
+    #[test]
+    fn parsing() {
+        for valid in ["0", "42", "CORE-42", "A1-7"] {
+            let id: TicketId = valid.parse().unwrap();
+            assert_eq!(id.to_string(), valid);
+            let json = serde_json::to_string(&id).unwrap();
+            assert_eq!(serde_json::from_str::<TicketId>(&json).unwrap(), id);
+        }
+        for invalid in [
+            "",
+            "-1",
+            "+1",
+            "core-1",
+            "CORE-",
+            "1CORE-1",
+            "CORE-X",
+            "ABCDEFGHIJK-1",
+        ] {
+            assert!(invalid.parse::<TicketId>().is_err(), "{invalid}");
+        }
+        // Ids serialized before project keys existed.
+        assert_eq!(
+            serde_json::from_str::<TicketId>("7").unwrap(),
+            TicketId::new(7)
+        );
+    }
+}
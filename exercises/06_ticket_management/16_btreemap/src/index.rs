@@ -0,0 +1,292 @@
+//! Secondary indexes maintained by `TicketStore`.
+//!
+//! Tickets are indexed by status and by the words of their title and description,
+//! so that `TicketStore::by_status` and `TicketStore::search` don't have to scan
+//! every ticket.
+
+use crate::{Status, Ticket, TicketId};
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+#[derive(Clone, Default)]
+pub(crate) struct TicketIndex {
+    by_status: HashMap<Status, BTreeSet<TicketId>>,
+    by_word: HashMap<String, BTreeSet<TicketId>>,
+    /// What each ticket was indexed under, to unindex it after it has changed.
+    entries: HashMap<TicketId, Entry>,
+}
+
+#[derive(Clone)]
+struct Entry {
+    status: Status,
+    words: HashSet<String>,
+}
+
+impl TicketIndex {
+    pub(crate) fn insert(&mut self, ticket: &Ticket) {
+        self.remove(ticket.id);
+        let words: HashSet<String> = ticket_words(ticket).collect();
+        self.by_status
+            .entry(ticket.status.clone())
+            .or_default()
+            .insert(ticket.id);
+        for word in &words {
+            self.by_word
+                .entry(word.clone())
+                .or_default()
+                .insert(ticket.id);
+        }
+        self.entries.insert(
+            ticket.id,
+            Entry {
+                status: ticket.status.clone(),
+                words,
+            },
+        );
+    }
+
+    pub(crate) fn remove(&mut self, id: TicketId) {
+        let Some(entry) = self.entries.remove(&id) else {
+            return;
+        };
+        remove_posting(&mut self.by_status, &entry.status, id);
+        for word in &entry.words {
+            remove_posting(&mut self.by_word, word, id);
+        }
+    }
+
+    /// The ids indexed under `status`, in ascending order.
+    pub(crate) fn with_status(&self, status: &Status) -> impl Iterator<Item = TicketId> + '_ {
+        self.by_status.get(status).into_iter().flatten().copied()
+    }
+
+    /// The ids indexed under every one of `terms`, in ascending order.
+    pub(crate) fn with_words(&self, terms: &[String]) -> Vec<TicketId> {
+        let mut postings = Vec::with_capacity(terms.len());
+        for term in terms {
+            match self.by_word.get(term) {
+                Some(posting) => postings.push(posting),
+                None => return Vec::new(),
+            }
+        }
+        // Walk the rarest word and probe the others.
+        postings.sort_by_key(|posting| posting.len());
+        let Some((rarest, others)) = postings.split_first() else {
+            return Vec::new();
+        };
+        rarest
+            .iter()
+            .filter(|id| others.iter().all(|posting| posting.contains(id)))
+            .copied()
+            .collect()
+    }
+}
+
+fn remove_posting<K, Q>(postings: &mut HashMap<K, BTreeSet<TicketId>>, key: &Q, id: TicketId)
+where
+    K: std::borrow::Borrow<Q> + std::hash::Hash + Eq,
+    Q: std::hash::Hash + Eq + ?Sized,
+{
+    if let Some(posting) = postings.get_mut(key) {
+        posting.remove(&id);
+        if posting.is_empty() {
+            postings.remove(key);
+        }
+    }
+}
+
+/// Splits `text` into lowercase words, dropping punctuation.
+pub(crate) fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Returns `true` if `ticket` contains every one of `terms`.
+pub(crate) fn matches_all(ticket: &Ticket, terms: &[String]) -> bool {
+    let words: HashSet<String> = ticket_words(ticket).collect();
+    terms.iter().all(|term| words.contains(term))
+}
+
+fn ticket_words(ticket: &Ticket) -> impl Iterator<Item = String> + '_ {
+    words(ticket.title.as_ref()).chain(words(ticket.description.as_ref()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        FieldChange, Status, Ticket, TicketDraft, TicketEventKind, TicketId, TicketPatch,
+        TicketStore, Workflow,
+    };
+    use proptest::prelude::*;
+    use ticket_fields::{TicketDescription, TicketTitle};
+
+    fn draft(title: &str, description: &str) -> TicketDraft {
+        TicketDraft {
+            title: TicketTitle::try_from(title).unwrap(),
+            description: TicketDescription::try_from(description).unwrap(),
+        }
+    }
+
+    fn ids(tickets: Vec<&Ticket>) -> Vec<TicketId> {
+        tickets.into_iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn search_and_filter() {
+        let mut store = TicketStore::new();
+        let login = store.add_ticket(draft("Login bug", "Users cannot log in."));
+        let logout = store.add_ticket(draft("Logout bug", "The session survives a logout."));
+        let docs = store.add_ticket(draft("Docs", "Document the login flow."));
+
+        assert_eq!(ids(store.search("login bug")), [login]);
+        assert_eq!(ids(store.search("BUG")), [login, logout]);
+        assert_eq!(ids(store.search("login")), [login, docs]);
+        assert!(store.search("").is_empty());
+        assert!(store.search("missing").is_empty());
+        assert_eq!(ids(store.by_status(Status::ToDo)), [login, logout, docs]);
+        assert!(store.by_status(Status::Done).is_empty());
+    }
+
+    #[test]
+    fn stays_consistent_across_mutations() {
+        let mut store = TicketStore::new();
+        let first = store.add_ticket(draft("Login bug", "Users cannot log in."));
+        let second = store.add_ticket(draft("Logout bug", "Sessions survive."));
+
+        store
+            .patch(TicketPatch {
+                id: first,
+                title: Some(TicketTitle::try_from("Signup bug").unwrap()),
+                description: None,
+                status: Some(Status::InProgress),
+            })
+            .unwrap();
+        assert_eq!(ids(store.by_status(Status::InProgress)), [first]);
+        assert_eq!(ids(store.by_status(Status::ToDo)), [second]);
+        assert_eq!(ids(store.search("signup")), [first]);
+        assert!(store.search("login").is_empty());
+
+        store.transition(first, Status::Done).unwrap();
+        store.transition(second, Status::InProgress).unwrap();
+        assert_eq!(ids(store.by_status(Status::Done)), [first]);
+        assert_eq!(ids(store.by_status(Status::InProgress)), [second]);
+        assert_eq!(ids(store.search("bug")), [first, second]);
+
+        store.remove(first);
+        assert!(store.by_status(Status::Done).is_empty());
+        assert_eq!(ids(store.search("bug")), [second]);
+    }
+
+    #[test]
+    fn tracks_edits_through_index_mut() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft("Login bug", "Users cannot log in."));
+        store[id].title = TicketTitle::try_from("Signup bug").unwrap();
+        assert_eq!(ids(store.search("signup")), [id]);
+        assert!(store.search("login").is_empty());
+
+        // The edit is recorded, and can be undone, once the next write starts.
+        store.add_ticket(draft("Docs", "Document the login flow."));
+        let last = store.history(id).last().unwrap();
+        assert_eq!(
+            last.kind,
+            TicketEventKind::Patched(vec![FieldChange::Title {
+                from: TicketTitle::try_from("Login bug").unwrap(),
+                to: TicketTitle::try_from("Signup bug").unwrap(),
+            }])
+        );
+        assert!(store.undo());
+        assert!(store.undo());
+        assert_eq!(store[id].title.as_ref(), "Login bug");
+        assert_eq!(ids(store.search("login")), [id]);
+    }
+
+    const STATUSES: [Status; 3] = [Status::ToDo, Status::InProgress, Status::Done];
+
+    fn status() -> impl Strategy<Value = Status> {
+        prop::sample::select(STATUSES.to_vec())
+    }
+
+    /// A workflow in which any status can follow any other.
+    fn anything_goes() -> Workflow {
+        let mut workflow = Workflow::empty();
+        for from in &STATUSES {
+            for to in &STATUSES {
+                workflow = workflow.allow(from.clone(), to.clone());
+            }
+        }
+        workflow
+    }
+
+    #[derive(Debug, Clone)]
+    enum Op {
+        Add(&'static str),
+        SetStatus(usize, Status),
+        SetTitle(usize, &'static str),
+        Remove(usize),
+    }
+
+    const TITLES: [&str; 4] = ["Login bug", "Logout bug", "Login page", "Docs"];
+
+    fn op() -> impl Strategy<Value = Op> {
+        let title = prop::sample::select(&TITLES[..]);
+        prop_oneof![
+            title.clone().prop_map(Op::Add),
+            (any::<usize>(), status()).prop_map(|(i, s)| Op::SetStatus(i, s)),
+            (any::<usize>(), title).prop_map(|(i, t)| Op::SetTitle(i, t)),
+            any::<usize>().prop_map(Op::Remove),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn matches_a_linear_scan(ops in prop::collection::vec(op(), 0..40)) {
+            let mut store = TicketStore::new().with_workflow(anything_goes());
+            for op in ops {
+                let ids: Vec<TicketId> = (&store).into_iter().map(|t| t.id).collect();
+                let pick = |i: usize| (!ids.is_empty()).then(|| ids[i % ids.len()]);
+                match op {
+                    Op::Add(title) => {
+                        store.add_ticket(draft(title, "A description"));
+                    }
+                    Op::SetStatus(i, status) => {
+                        if let Some(id) = pick(i) {
+                            store.patch(TicketPatch {
+                                id,
+                                title: None,
+                                description: None,
+                                status: Some(status),
+                            }).unwrap();
+                        }
+                    }
+                    Op::SetTitle(i, title) => {
+                        if let Some(id) = pick(i) {
+                            store.patch(TicketPatch {
+                                id,
+                                title: Some(TicketTitle::try_from(title).unwrap()),
+                                description: None,
+                                status: None,
+                            }).unwrap();
+                        }
+                    }
+                    Op::Remove(i) => {
+                        if let Some(id) = pick(i) {
+                            store.remove(id);
+                        }
+                    }
+                }
+
+                for status in STATUSES {
+                    let expected: Vec<&Ticket> =
+                        (&store).into_iter().filter(|t| t.status == status).collect();
+                    prop_assert_eq!(store.by_status(status.clone()), expected);
+                }
+                let expected: Vec<&Ticket> = (&store)
+                    .into_iter()
+                    .filter(|t| t.title.as_ref().to_lowercase().contains("login"))
+                    .collect();
+                prop_assert_eq!(store.search("login"), expected);
+            }
+        }
+    }
+}
@@ -3,38 +3,157 @@
 //  references to the tickets, ordered by their `TicketId`.
 //  Implement additional traits on `TicketId` if needed.
 
-use std::collections::BTreeMap;
+use history::{History, Stamp};
+use index::TicketIndex;
+use links::Links;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
 use std::ops::{Index, IndexMut};
 use ticket_fields::{TicketDescription, TicketTitle};
+use ticket_repository::TicketRepository;
+use undo::UndoLog;
+
+mod bulk;
+mod durable;
+mod history;
+mod ids;
+mod index;
+mod links;
+mod pagination;
+mod query;
+mod undo;
+mod validation;
+mod workflow;
+
+pub use bulk::{BulkError, PatchError};
+pub use durable::{DurableTicketStore, RecoveryReport, WalError};
+pub use history::{Clock, FieldChange, ReplayError, SystemClock, TicketEvent, TicketEventKind};
+pub use ids::{IdStrategy, ParseProjectKeyError, ParseTicketIdError, ProjectKey};
+pub use links::{LinkError, TicketLink};
+pub use pagination::{Cursor, CursorError, CursorKey, Direction, Page, PageOptions};
+pub use query::{
+    Comparison, Filter, Predicate, Query, QueryError, QueryErrorKind, Sort, SortKey, Span,
+    TextMatch,
+};
+pub use undo::TicketCommand;
+pub use validation::{FieldError, FieldErrorKind, ValidationReport};
+pub use workflow::{TransitionError, Workflow, WorkflowConfigError};
 
 #[derive(Clone)]
 pub struct TicketStore {
     tickets: BTreeMap<TicketId, Ticket>,
+    /// The smallest number that the sequential id strategies may still use.
     counter: u64,
+    /// The ids that [`IdStrategy::Random`] has handed out or seen restored.
+    random_ids: HashSet<TicketId>,
+    id_strategy: IdStrategy,
+    index: TicketIndex,
+    /// The ticket last handed out through `get_mut`, as it was at the time.
+    ///
+    /// The `&mut` borrow ends before any other `&mut self` method can run,
+    /// so at most one ticket is ever stale: its edits are indexed and recorded
+    /// at the start of the next write, and queries check it directly in the meantime.
+    stale: Option<(Ticket, Stamp)>,
+    workflow: Workflow,
+    history: History,
+    undo: UndoLog,
+    links: Links,
+    /// The key that pagination cursors are signed with.
+    cursor_key: CursorKey,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
-pub struct TicketId(u64);
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TicketId {
+    project: Option<ProjectKey>,
+    number: u64,
+}
 
-#[derive(Clone, Debug, PartialEq)]
+/// A stored ticket.
+///
+/// Its id and status can only be changed by the store, so that status changes
+/// follow the workflow even through `IndexMut`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Ticket {
-    pub id: TicketId,
+    id: TicketId,
     pub title: TicketTitle,
     pub description: TicketDescription,
-    pub status: Status,
+    status: Status,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+impl Ticket {
+    pub fn id(&self) -> TicketId {
+        self.id
+    }
+
+    pub fn status(&self) -> &Status {
+        &self.status
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TicketDraft {
     pub title: TicketTitle,
     pub description: TicketDescription,
 }
 
-#[derive(Clone, Debug, Copy, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TicketPatch {
+    pub id: TicketId,
+    pub title: Option<TicketTitle>,
+    pub description: Option<TicketDescription>,
+    pub status: Option<Status>,
+}
+
+/// The state of a ticket in its [`Workflow`].
+///
+/// Statuses have no order of their own: [`Workflow::states`] orders them.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum Status {
     ToDo,
     InProgress,
     Done,
+    /// A state declared in a custom workflow configuration.
+    Custom(String),
+}
+
+impl std::fmt::Display for Status {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Status::ToDo => f.write_str("ToDo"),
+            Status::InProgress => f.write_str("InProgress"),
+            Status::Done => f.write_str("Done"),
+            Status::Custom(name) => f.write_str(name),
+        }
+    }
+}
+
+impl TryFrom<String> for Status {
+    type Error = ParseStatusError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl TryFrom<&str> for Status {
+    type Error = ParseStatusError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "todo" => Ok(Status::ToDo),
+            "inprogress" => Ok(Status::InProgress),
+            "done" => Ok(Status::Done),
+            _ => Err(ParseStatusError {
+                invalid_status: value.to_string(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{invalid_status}` is not a valid status. Use one of: ToDo, InProgress, Done")]
+pub struct ParseStatusError {
+    invalid_status: String,
 }
 
 impl TicketStore {
@@ -42,20 +161,46 @@ impl TicketStore {
         Self {
             tickets: BTreeMap::new(),
             counter: 0,
+            random_ids: HashSet::new(),
+            id_strategy: IdStrategy::default(),
+            index: TicketIndex::default(),
+            stale: None,
+            workflow: Workflow::default(),
+            history: History::default(),
+            undo: UndoLog::default(),
+            links: Links::default(),
+            cursor_key: CursorKey::random(),
         }
     }
 
+    /// Makes every status change follow `workflow`.
+    pub fn with_workflow(mut self, workflow: Workflow) -> Self {
+        self.workflow = workflow;
+        self
+    }
+
+    /// Timestamps the recorded events with `clock`.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.history.set_clock(clock);
+        self
+    }
+
+    pub fn workflow(&self) -> &Workflow {
+        &self.workflow
+    }
+
     pub fn add_ticket(&mut self, ticket: TicketDraft) -> TicketId {
-        let id = TicketId(self.counter);
-        self.counter += 1;
+        self.reindex_stale();
+        let id = self.generate_id();
         let ticket = Ticket {
             id,
             title: ticket.title,
             description: ticket.description,
             status: Status::ToDo,
         };
+        self.commit(TicketCommand::Insert(ticket.clone()));
+        self.index.insert(&ticket);
         self.tickets.insert(id, ticket);
-        self.counter += 1;
         id
     }
 
@@ -63,8 +208,194 @@ impl TicketStore {
         self.tickets.get(&id)
     }
 
+    /// Gives mutable access to the title and description of a ticket.
+    ///
+    /// The edits are recorded as a patch, stamped now, when the next write starts.
     pub fn get_mut(&mut self, id: TicketId) -> Option<&mut Ticket> {
-        self.tickets.get_mut(&id)
+        self.reindex_stale();
+        let stamp = self.history.stamp();
+        let ticket = self.tickets.get_mut(&id)?;
+        self.stale = Some((ticket.clone(), stamp));
+        Some(ticket)
+    }
+
+    /// Removes a ticket, along with its links to other tickets.
+    pub fn remove(&mut self, id: TicketId) -> Option<Ticket> {
+        self.reindex_stale();
+        self.get(id)?;
+        self.transaction(|store| {
+            store.unlink_all(id);
+            let ticket = store.remove_ticket(id)?;
+            store.commit(TicketCommand::Delete(ticket.clone()));
+            Some(ticket)
+        })
+    }
+
+    fn remove_ticket(&mut self, id: TicketId) -> Option<Ticket> {
+        self.index.remove(id);
+        self.tickets.remove(&id)
+    }
+
+    /// Applies the fields set in `patch`, returning the updated ticket.
+    ///
+    /// A new status must follow a transition of the workflow.
+    pub fn patch(&mut self, patch: TicketPatch) -> Result<&Ticket, PatchError> {
+        self.reindex_stale();
+        let id = patch.id;
+        self.check_patch(&patch)?;
+        let before = undo::revert(&self.tickets[&id], &patch);
+        self.apply_patch(patch.clone());
+        self.commit(TicketCommand::Patch {
+            before,
+            after: patch,
+        });
+        Ok(&self.tickets[&id])
+    }
+
+    /// Checks that `patch` can be applied.
+    pub(crate) fn check_patch(&self, patch: &TicketPatch) -> Result<(), PatchError> {
+        let ticket = self
+            .get(patch.id)
+            .ok_or(PatchError::TicketNotFound(patch.id))?;
+        match &patch.status {
+            Some(to) if !self.workflow.permits(&ticket.status, to) => {
+                Err(PatchError::InvalidTransition {
+                    from: ticket.status.clone(),
+                    to: to.clone(),
+                })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn apply_patch(&mut self, patch: TicketPatch) -> Option<&Ticket> {
+        let ticket = self.tickets.get_mut(&patch.id)?;
+        if let Some(title) = patch.title {
+            ticket.title = title;
+        }
+        if let Some(description) = patch.description {
+            ticket.description = description;
+        }
+        if let Some(status) = patch.status {
+            ticket.status = status;
+        }
+        self.index.insert(ticket);
+        Some(ticket)
+    }
+
+    /// Moves a ticket to the `to` status, if the workflow allows it.
+    pub fn transition(&mut self, id: TicketId, to: Status) -> Result<&Ticket, TransitionError> {
+        self.reindex_stale();
+        self.check_transition(id, &to)?;
+        let ticket = self.tickets.get_mut(&id).unwrap();
+        let from = std::mem::replace(&mut ticket.status, to.clone());
+        self.index.insert(ticket);
+        self.commit(TicketCommand::Transition { id, from, to });
+        Ok(&self.tickets[&id])
+    }
+
+    /// Checks that the ticket with the given id can move to the `to` status.
+    pub(crate) fn check_transition(
+        &self,
+        id: TicketId,
+        to: &Status,
+    ) -> Result<(), TransitionError> {
+        let ticket = self.get(id).ok_or(TransitionError::TicketNotFound(id))?;
+        if !self.workflow.allows(&ticket.status, to) {
+            return Err(TransitionError::InvalidTransition {
+                from: ticket.status.clone(),
+                to: to.clone(),
+            });
+        }
+        Ok(())
+    }
+
+    /// The tickets with the given status, ordered by their `TicketId`.
+    pub fn by_status(&self, status: Status) -> Vec<&Ticket> {
+        let mut tickets: Vec<&Ticket> = self
+            .index
+            .with_status(&status)
+            .filter(|id| Some(*id) != self.stale_id())
+            .map(|id| &self.tickets[&id])
+            .collect();
+        if let Some(ticket) = self.stale_ticket() {
+            if ticket.status == status {
+                insert_sorted(&mut tickets, ticket);
+            }
+        }
+        tickets
+    }
+
+    /// The tickets whose title or description contains every word in `query`,
+    /// ordered by their `TicketId`.
+    ///
+    /// Words are matched whole and case-insensitively.
+    pub fn search(&self, query: &str) -> Vec<&Ticket> {
+        let terms: Vec<String> = index::words(query).collect();
+        if terms.is_empty() {
+            return Vec::new();
+        }
+        let mut tickets: Vec<&Ticket> = self
+            .index
+            .with_words(&terms)
+            .into_iter()
+            .filter(|id| Some(*id) != self.stale_id())
+            .map(|id| &self.tickets[&id])
+            .collect();
+        if let Some(ticket) = self.stale_ticket() {
+            if index::matches_all(ticket, &terms) {
+                insert_sorted(&mut tickets, ticket);
+            }
+        }
+        tickets
+    }
+
+    fn stale_id(&self) -> Option<TicketId> {
+        self.stale.as_ref().map(|(before, _)| before.id)
+    }
+
+    fn stale_ticket(&self) -> Option<&Ticket> {
+        self.stale_id().and_then(|id| self.tickets.get(&id))
+    }
+
+    /// Indexes and records the edits made to the ticket last handed out by `get_mut`.
+    pub(crate) fn reindex_stale(&mut self) {
+        let Some((before, stamp)) = self.stale.take() else {
+            return;
+        };
+        let Some(after) = self.tickets.get(&before.id).cloned() else {
+            return;
+        };
+        if after == before {
+            return;
+        }
+        self.index.insert(&after);
+        let patch = TicketPatch {
+            id: after.id,
+            title: (after.title != before.title).then(|| after.title.clone()),
+            description: (after.description != before.description)
+                .then(|| after.description.clone()),
+            status: (after.status != before.status).then(|| after.status.clone()),
+        };
+        let command = TicketCommand::Patch {
+            before: undo::revert(&before, &patch),
+            after: patch,
+        };
+        self.stamped(stamp, |store| store.commit(command));
+    }
+
+    /// Inserts a fully-formed ticket, e.g. when restoring a store from disk.
+    pub(crate) fn restore(&mut self, ticket: Ticket) {
+        self.reindex_stale();
+        self.history
+            .record(ticket.id, TicketEventKind::Created(ticket.clone()));
+        self.insert_ticket(ticket);
+    }
+
+    fn insert_ticket(&mut self, ticket: Ticket) {
+        self.index.insert(&ticket);
+        self.reserve(ticket.id);
+        self.tickets.insert(ticket.id, ticket);
     }
 }
 
@@ -96,6 +427,11 @@ impl IndexMut<&TicketId> for TicketStore {
     }
 }
 
+fn insert_sorted<'a>(tickets: &mut Vec<&'a Ticket>, ticket: &'a Ticket) {
+    let position = tickets.partition_point(|t| t.id < ticket.id);
+    tickets.insert(position, ticket);
+}
+
 impl<'a> IntoIterator for &'a TicketStore {
     type Item = &'a Ticket;
     type IntoIter = std::collections::btree_map::Values<'a, TicketId, Ticket>;
@@ -104,10 +440,43 @@ impl<'a> IntoIterator for &'a TicketStore {
     }
 }
 
+impl TicketRepository for TicketStore {
+    type Id = TicketId;
+    type Ticket = Ticket;
+    type Draft = TicketDraft;
+    type Iter<'a> = std::collections::btree_map::Values<'a, TicketId, Ticket>;
+
+    fn add_ticket(&mut self, draft: TicketDraft) -> TicketId {
+        TicketStore::add_ticket(self, draft)
+    }
+
+    fn get(&self, id: TicketId) -> Option<&Ticket> {
+        TicketStore::get(self, id)
+    }
+
+    fn get_mut(&mut self, id: TicketId) -> Option<&mut Ticket> {
+        TicketStore::get_mut(self, id)
+    }
+
+    fn remove(&mut self, id: TicketId) -> Option<Ticket> {
+        TicketStore::remove(self, id)
+    }
+
+    /// Iterates over the tickets, ordered by their `TicketId`.
+    fn iter(&self) -> Self::Iter<'_> {
+        self.tickets.values()
+    }
+
+    fn len(&self) -> usize {
+        self.tickets.len()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::{Status, TicketDraft, TicketId, TicketStore};
-    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+    use proptest::prelude::*;
+    use ticket_fields::test_helpers::{strategies, ticket_description, ticket_title};
 
     #[test]
     fn works() {
@@ -141,4 +510,41 @@ mod tests {
         };
         assert_eq!(ids, sorted_ids);
     }
+
+    proptest! {
+        #[test]
+        fn stores_arbitrary_drafts(
+            drafts in prop::collection::vec(
+                (strategies::ticket_title(), strategies::ticket_description()),
+                0..20,
+            )
+        ) {
+            let mut store = TicketStore::new();
+            for (title, description) in drafts {
+                let draft = TicketDraft { title, description };
+                let id = store.add_ticket(draft.clone());
+                prop_assert_eq!(&store[id].title, &draft.title);
+                prop_assert_eq!(&store[id].description, &draft.description);
+            }
+        }
+    }
+
+    mod conformance {
+        use crate::{Ticket, TicketDraft, TicketStore};
+        use ticket_fields::test_helpers::{ticket_description, ticket_title};
+        use ticket_fields::TicketTitle;
+
+        fn draft() -> TicketDraft {
+            TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            }
+        }
+
+        fn edit(ticket: &mut Ticket) {
+            ticket.title = TicketTitle::try_from("Edited").unwrap();
+        }
+
+        ticket_repository::conformance_tests!(TicketStore::new, draft, edit);
+    }
 }
@@ -0,0 +1,445 @@
+//! Relationships between tickets: parent/child and "blocks/blocked-by".
+//!
+//! Both kinds of links form a DAG: a link that would close a cycle is rejected.
+//! When a ticket is removed, its links are dropped: its children become top-level
+//! tickets and the tickets it blocked are no longer waiting on it.
+//! `remove_tree` removes a ticket together with all of its descendants instead.
+
+use crate::{Status, Ticket, TicketCommand, TicketId, TicketStore};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TicketLink {
+    /// `child` is a part of `parent`.
+    Parent { child: TicketId, parent: TicketId },
+    /// `blocked` cannot start until `blocker` is done.
+    Blocks {
+        blocker: TicketId,
+        blocked: TicketId,
+    },
+}
+
+impl TicketLink {
+    /// The ticket that depends on the other end of the link.
+    pub fn dependent(&self) -> TicketId {
+        match self {
+            TicketLink::Parent { child, .. } => *child,
+            TicketLink::Blocks { blocked, .. } => *blocked,
+        }
+    }
+}
+
+impl fmt::Display for TicketLink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TicketLink::Parent { child, parent } => write!(f, "{parent} is the parent of {child}"),
+            TicketLink::Blocks { blocker, blocked } => write!(f, "{blocker} blocks {blocked}"),
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum LinkError {
+    #[error("There is no ticket with id {0}")]
+    TicketNotFound(TicketId),
+    #[error("Ticket {child} already has a parent, {parent}")]
+    AlreadyHasParent { child: TicketId, parent: TicketId },
+    #[error("Linking would create a cycle: {0}")]
+    Cycle(TicketLink),
+}
+
+#[derive(Clone, Default)]
+pub(crate) struct Links {
+    parent: HashMap<TicketId, TicketId>,
+    children: HashMap<TicketId, BTreeSet<TicketId>>,
+    /// The tickets blocking each ticket.
+    blockers: HashMap<TicketId, BTreeSet<TicketId>>,
+    /// The tickets blocked by each ticket.
+    dependents: HashMap<TicketId, BTreeSet<TicketId>>,
+}
+
+impl Links {
+    pub(crate) fn insert(&mut self, link: TicketLink) -> bool {
+        match link {
+            TicketLink::Parent { child, parent } => {
+                self.parent.insert(child, parent);
+                self.children.entry(parent).or_default().insert(child)
+            }
+            TicketLink::Blocks { blocker, blocked } => {
+                self.dependents.entry(blocker).or_default().insert(blocked);
+                self.blockers.entry(blocked).or_default().insert(blocker)
+            }
+        }
+    }
+
+    pub(crate) fn remove(&mut self, link: TicketLink) -> bool {
+        match link {
+            TicketLink::Parent { child, parent } => {
+                if self.parent.get(&child) != Some(&parent) {
+                    return false;
+                }
+                self.parent.remove(&child);
+                remove_edge(&mut self.children, parent, child)
+            }
+            TicketLink::Blocks { blocker, blocked } => {
+                remove_edge(&mut self.dependents, blocker, blocked);
+                remove_edge(&mut self.blockers, blocked, blocker)
+            }
+        }
+    }
+
+    pub(crate) fn contains(&self, link: TicketLink) -> bool {
+        match link {
+            TicketLink::Parent { child, parent } => self.parent.get(&child) == Some(&parent),
+            TicketLink::Blocks { blocker, blocked } => self
+                .blockers
+                .get(&blocked)
+                .is_some_and(|blockers| blockers.contains(&blocker)),
+        }
+    }
+
+    pub(crate) fn all(&self) -> Vec<TicketLink> {
+        let parents = self
+            .parent
+            .iter()
+            .map(|(&child, &parent)| TicketLink::Parent { child, parent });
+        let blocks = self.blockers.iter().flat_map(|(&blocked, blockers)| {
+            blockers
+                .iter()
+                .map(move |&blocker| TicketLink::Blocks { blocker, blocked })
+        });
+        parents.chain(blocks).collect()
+    }
+
+    /// Every link that involves `id`.
+    fn of(&self, id: TicketId) -> Vec<TicketLink> {
+        let parent = self
+            .parent
+            .get(&id)
+            .map(|&parent| TicketLink::Parent { child: id, parent });
+        let children =
+            edges(&self.children, id).map(|child| TicketLink::Parent { child, parent: id });
+        let blockers = edges(&self.blockers, id).map(|blocker| TicketLink::Blocks {
+            blocker,
+            blocked: id,
+        });
+        let dependents = edges(&self.dependents, id).map(|blocked| TicketLink::Blocks {
+            blocker: id,
+            blocked,
+        });
+        parent
+            .into_iter()
+            .chain(children)
+            .chain(blockers)
+            .chain(dependents)
+            .collect()
+    }
+
+    /// Returns `true` if adding `link` would close a cycle.
+    fn would_cycle(&self, link: TicketLink) -> bool {
+        match link {
+            TicketLink::Parent { child, parent } => {
+                let mut ancestor = Some(parent);
+                while let Some(id) = ancestor {
+                    if id == child {
+                        return true;
+                    }
+                    ancestor = self.parent.get(&id).copied();
+                }
+                false
+            }
+            // A cycle if `blocked` already blocks `blocker`, directly or not.
+            TicketLink::Blocks { blocker, blocked } => {
+                let mut stack = vec![blocked];
+                let mut visited = BTreeSet::new();
+                while let Some(id) = stack.pop() {
+                    if id == blocker {
+                        return true;
+                    }
+                    if visited.insert(id) {
+                        stack.extend(edges(&self.dependents, id));
+                    }
+                }
+                false
+            }
+        }
+    }
+}
+
+fn edges(
+    edges: &HashMap<TicketId, BTreeSet<TicketId>>,
+    id: TicketId,
+) -> impl Iterator<Item = TicketId> + '_ {
+    edges.get(&id).into_iter().flatten().copied()
+}
+
+fn remove_edge(
+    edges: &mut HashMap<TicketId, BTreeSet<TicketId>>,
+    from: TicketId,
+    to: TicketId,
+) -> bool {
+    let Some(targets) = edges.get_mut(&from) else {
+        return false;
+    };
+    let removed = targets.remove(&to);
+    if targets.is_empty() {
+        edges.remove(&from);
+    }
+    removed
+}
+
+impl TicketStore {
+    /// Links two tickets. Returns `Ok(false)` if they were already linked this way.
+    pub fn link(&mut self, link: TicketLink) -> Result<bool, LinkError> {
+        self.reindex_stale();
+        if !self.check_link(link)? {
+            return Ok(false);
+        }
+        self.links.insert(link);
+        self.commit(TicketCommand::Link(link));
+        Ok(true)
+    }
+
+    /// Checks that `link` can be added. Returns `Ok(false)` if it's already there.
+    pub(crate) fn check_link(&self, link: TicketLink) -> Result<bool, LinkError> {
+        let (a, b) = match link {
+            TicketLink::Parent { child, parent } => (child, parent),
+            TicketLink::Blocks { blocker, blocked } => (blocker, blocked),
+        };
+        for id in [a, b] {
+            if self.get(id).is_none() {
+                return Err(LinkError::TicketNotFound(id));
+            }
+        }
+        if self.links.contains(link) {
+            return Ok(false);
+        }
+        if let TicketLink::Parent { child, .. } = link {
+            if let Some(&parent) = self.links.parent.get(&child) {
+                return Err(LinkError::AlreadyHasParent { child, parent });
+            }
+        }
+        if self.links.would_cycle(link) {
+            return Err(LinkError::Cycle(link));
+        }
+        Ok(true)
+    }
+
+    /// Removes a link. Returns `false` if the tickets weren't linked this way.
+    pub fn unlink(&mut self, link: TicketLink) -> bool {
+        self.reindex_stale();
+        if !self.links.remove(link) {
+            return false;
+        }
+        self.commit(TicketCommand::Unlink(link));
+        true
+    }
+
+    /// Removes the ticket's links, as a step of removing the ticket.
+    pub(crate) fn unlink_all(&mut self, id: TicketId) {
+        for link in self.links.of(id) {
+            self.unlink(link);
+        }
+    }
+
+    pub fn parent(&self, id: TicketId) -> Option<TicketId> {
+        self.links.parent.get(&id).copied()
+    }
+
+    pub fn children(&self, id: TicketId) -> impl Iterator<Item = TicketId> + '_ {
+        edges(&self.links.children, id)
+    }
+
+    /// The tickets that must be done before `id` can start.
+    pub fn blockers(&self, id: TicketId) -> impl Iterator<Item = TicketId> + '_ {
+        edges(&self.links.blockers, id)
+    }
+
+    /// The tickets waiting on `id`.
+    pub fn dependents(&self, id: TicketId) -> impl Iterator<Item = TicketId> + '_ {
+        edges(&self.links.dependents, id)
+    }
+
+    /// The `ToDo` tickets whose blockers are all `Done`, ordered by their `TicketId`.
+    pub fn ready_to_start(&self) -> Vec<&Ticket> {
+        self.by_status(Status::ToDo)
+            .into_iter()
+            .filter(|ticket| {
+                self.blockers(ticket.id)
+                    .all(|blocker| self[blocker].status == Status::Done)
+            })
+            .collect()
+    }
+
+    /// The tickets that aren't `Done`, each one after all of its blockers.
+    ///
+    /// Among the tickets that are free to go next, the lowest `TicketId` goes first.
+    pub fn open_work(&self) -> Vec<&Ticket> {
+        let open = |id: &TicketId| self[*id].status != Status::Done;
+        let mut waiting_on: HashMap<TicketId, usize> = HashMap::new();
+        let mut ready = BTreeSet::new();
+        for ticket in self.into_iter().filter(|ticket| open(&ticket.id)) {
+            match self.blockers(ticket.id).filter(open).count() {
+                0 => {
+                    ready.insert(ticket.id);
+                }
+                n => {
+                    waiting_on.insert(ticket.id, n);
+                }
+            }
+        }
+
+        let mut order = Vec::with_capacity(ready.len() + waiting_on.len());
+        while let Some(id) = ready.pop_first() {
+            order.push(&self[id]);
+            for dependent in self.dependents(id) {
+                if let Some(count) = waiting_on.get_mut(&dependent) {
+                    *count -= 1;
+                    if *count == 0 {
+                        waiting_on.remove(&dependent);
+                        ready.insert(dependent);
+                    }
+                }
+            }
+        }
+        order
+    }
+
+    /// Removes a ticket and all of its descendants, returning them children first.
+    ///
+    /// The removal is undone as a unit.
+    pub fn remove_tree(&mut self, id: TicketId) -> Vec<Ticket> {
+        self.transaction(|store| {
+            let mut removed = Vec::new();
+            let mut stack = vec![(id, false)];
+            while let Some((id, expanded)) = stack.pop() {
+                if expanded {
+                    removed.extend(store.remove(id));
+                } else {
+                    stack.push((id, true));
+                    stack.extend(store.children(id).map(|child| (child, false)));
+                }
+            }
+            removed
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TicketDraft, Workflow};
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn store_with(n: usize) -> (TicketStore, Vec<TicketId>) {
+        let mut store = TicketStore::new();
+        let ids = (0..n)
+            .map(|_| {
+                store.add_ticket(TicketDraft {
+                    title: ticket_title(),
+                    description: ticket_description(),
+                })
+            })
+            .collect();
+        (store, ids)
+    }
+
+    fn blocks(blocker: TicketId, blocked: TicketId) -> TicketLink {
+        TicketLink::Blocks { blocker, blocked }
+    }
+
+    fn parent(child: TicketId, parent: TicketId) -> TicketLink {
+        TicketLink::Parent { child, parent }
+    }
+
+    fn ids(tickets: Vec<&Ticket>) -> Vec<TicketId> {
+        tickets.into_iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn rejects_cycles() {
+        let (mut store, t) = store_with(3);
+        store.link(blocks(t[0], t[1])).unwrap();
+        store.link(blocks(t[1], t[2])).unwrap();
+        assert_eq!(store.link(blocks(t[0], t[1])), Ok(false));
+        assert_eq!(
+            store.link(blocks(t[2], t[0])),
+            Err(LinkError::Cycle(blocks(t[2], t[0])))
+        );
+        assert!(store.link(blocks(t[1], t[1])).is_err());
+
+        store.link(parent(t[1], t[0])).unwrap();
+        store.link(parent(t[2], t[1])).unwrap();
+        assert!(matches!(
+            store.link(parent(t[0], t[2])),
+            Err(LinkError::Cycle(_))
+        ));
+        assert_eq!(
+            store.link(parent(t[2], t[0])),
+            Err(LinkError::AlreadyHasParent {
+                child: t[2],
+                parent: t[1]
+            })
+        );
+        assert_eq!(
+            store.link(parent(t[0], TicketId::new(999))),
+            Err(LinkError::TicketNotFound(TicketId::new(999)))
+        );
+    }
+
+    #[test]
+    fn ready_to_start_and_open_work() {
+        let (mut store, t) = store_with(4);
+        // 3 -> 1 -> 0, 2 -> 0: ticket 0 waits on 1 and 2, which both wait on 3.
+        store.link(blocks(t[1], t[0])).unwrap();
+        store.link(blocks(t[2], t[0])).unwrap();
+        store.link(blocks(t[3], t[1])).unwrap();
+
+        assert_eq!(ids(store.ready_to_start()), [t[2], t[3]]);
+        assert_eq!(ids(store.open_work()), [t[2], t[3], t[1], t[0]]);
+
+        for status in [Status::InProgress, Status::Done] {
+            store.transition(t[3], status).unwrap();
+        }
+        assert_eq!(ids(store.ready_to_start()), [t[1], t[2]]);
+        assert_eq!(ids(store.open_work()), [t[1], t[2], t[0]]);
+    }
+
+    #[test]
+    fn removing_a_ticket_drops_its_links() {
+        let (mut store, t) = store_with(3);
+        store.link(parent(t[1], t[0])).unwrap();
+        store.link(blocks(t[0], t[2])).unwrap();
+
+        store.remove(t[0]);
+        assert_eq!(store.parent(t[1]), None);
+        assert_eq!(store.blockers(t[2]).count(), 0);
+        assert_eq!(ids(store.ready_to_start()), [t[1], t[2]]);
+
+        // Undoing the removal restores the links too.
+        assert!(store.undo());
+        assert_eq!(store.parent(t[1]), Some(t[0]));
+        assert_eq!(store.blockers(t[2]).collect::<Vec<_>>(), [t[0]]);
+        let replayed = TicketStore::replay(Workflow::default(), store.events()).unwrap();
+        assert_eq!(replayed.parent(t[1]), Some(t[0]));
+    }
+
+    #[test]
+    fn remove_tree() {
+        let (mut store, t) = store_with(4);
+        store.link(parent(t[1], t[0])).unwrap();
+        store.link(parent(t[2], t[1])).unwrap();
+        store.link(blocks(t[2], t[3])).unwrap();
+
+        let removed: Vec<TicketId> = store.remove_tree(t[0]).iter().map(|t| t.id).collect();
+        assert_eq!(removed, [t[2], t[1], t[0]]);
+        assert_eq!(ids((&store).into_iter().collect()), [t[3]]);
+        assert_eq!(store.blockers(t[3]).count(), 0);
+
+        assert!(store.undo());
+        assert_eq!(store.children(t[0]).collect::<Vec<_>>(), [t[1]]);
+        assert_eq!(store.dependents(t[2]).collect::<Vec<_>>(), [t[3]]);
+    }
+}
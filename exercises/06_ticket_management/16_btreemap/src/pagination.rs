@@ -0,0 +1,346 @@
+//! Cursor-based pagination over a `TicketStore`.
+//!
+//! A page starts right after a `TicketId` rather than at an offset,
+//! so tickets inserted or removed between two requests never shift a page:
+//! nothing is skipped and nothing is returned twice.
+
+use crate::{Status, Ticket, TicketId, TicketStore};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fmt;
+use std::ops::Bound;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Direction {
+    /// By ascending `TicketId`.
+    #[default]
+    Forward,
+    /// By descending `TicketId`.
+    Backward,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct PageOptions {
+    pub direction: Direction,
+    /// Only return tickets with this status.
+    pub status: Option<Status>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Page<'a> {
+    pub tickets: Vec<&'a Ticket>,
+    /// Where the next page starts, or `None` if this is the last page.
+    pub next: Option<Cursor>,
+}
+
+/// An opaque position in a paginated listing.
+///
+/// A cursor can only be used with a store that has the key it was signed with:
+/// the store that issued it, its clones, or stores configured with the same key.
+/// An edited cursor is rejected.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Cursor(String);
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum CursorError {
+    #[error("The cursor is malformed")]
+    Malformed,
+    #[error("The cursor was not issued by this store, or it was modified")]
+    InvalidSignature,
+}
+
+/// The secret that pagination cursors are signed with.
+#[derive(Clone)]
+pub struct CursorKey(Vec<u8>);
+
+impl CursorKey {
+    /// A key made of `bytes`. Use at least 32 random bytes.
+    pub fn new(bytes: impl Into<Vec<u8>>) -> Self {
+        CursorKey(bytes.into())
+    }
+
+    /// A fresh random key.
+    pub fn random() -> Self {
+        let mut bytes = vec![0; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        CursorKey(bytes)
+    }
+
+    fn mac(&self, payload: &str) -> Hmac<Sha256> {
+        let mut mac =
+            Hmac::<Sha256>::new_from_slice(&self.0).expect("HMAC accepts keys of any length");
+        mac.update(payload.as_bytes());
+        mac
+    }
+}
+
+impl fmt::Debug for CursorKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("CursorKey(..)")
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct Position {
+    /// `None` for a position before the first ticket.
+    after: Option<TicketId>,
+    direction: Direction,
+    status: Option<Status>,
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = CursorError;
+
+    /// Parses a cursor received from a client. Its signature is checked when it's used.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Cursor(s.to_string()))
+    }
+}
+
+impl TicketStore {
+    /// Signs pagination cursors with `key`, e.g. so that they stay valid across restarts.
+    /// By default, every store gets a random key.
+    pub fn with_cursor_key(mut self, key: CursorKey) -> Self {
+        self.cursor_key = key;
+        self
+    }
+
+    /// Up to `limit` tickets with an id greater than `after`, ordered by id.
+    pub fn page(&self, after: Option<TicketId>, limit: usize) -> Page<'_> {
+        self.page_with(after, limit, &PageOptions::default())
+    }
+
+    /// Up to `limit` tickets past `after` in the given direction, matching `options`.
+    ///
+    /// With a `limit` of zero, the page is empty and `next` starts where it did.
+    pub fn page_with(
+        &self,
+        after: Option<TicketId>,
+        limit: usize,
+        options: &PageOptions,
+    ) -> Page<'_> {
+        let start = after.map_or(Bound::Unbounded, Bound::Excluded);
+        let range = match options.direction {
+            Direction::Forward => self.tickets.range((start, Bound::Unbounded)),
+            Direction::Backward => self.tickets.range((Bound::Unbounded, start)),
+        };
+        let matching =
+            |ticket: &&Ticket| options.status.as_ref().is_none_or(|s| &ticket.status == s);
+        // Fetch one extra ticket to know whether there is a next page.
+        let mut tickets: Vec<&Ticket> = match options.direction {
+            Direction::Forward => range
+                .map(|(_, t)| t)
+                .filter(matching)
+                .take(limit.saturating_add(1))
+                .collect(),
+            Direction::Backward => range
+                .rev()
+                .map(|(_, t)| t)
+                .filter(matching)
+                .take(limit.saturating_add(1))
+                .collect(),
+        };
+        let next = if tickets.len() > limit {
+            tickets.truncate(limit);
+            Some(self.sign(&Position {
+                after: tickets.last().map_or(after, |last| Some(last.id)),
+                direction: options.direction,
+                status: options.status.clone(),
+            }))
+        } else {
+            None
+        };
+        Page { tickets, next }
+    }
+
+    /// The page following `cursor`, with the same direction and filter as the page
+    /// that issued it.
+    pub fn next_page(&self, cursor: &Cursor, limit: usize) -> Result<Page<'_>, CursorError> {
+        let position = self.verify(cursor)?;
+        let options = PageOptions {
+            direction: position.direction,
+            status: position.status,
+        };
+        Ok(self.page_with(position.after, limit, &options))
+    }
+
+    fn sign(&self, position: &Position) -> Cursor {
+        let payload = hex(&serde_json::to_vec(position).expect("a position can always be encoded"));
+        let signature = hex(&self.cursor_key.mac(&payload).finalize().into_bytes());
+        Cursor(format!("{payload}.{signature}"))
+    }
+
+    fn verify(&self, cursor: &Cursor) -> Result<Position, CursorError> {
+        let (payload, signature) = cursor.0.split_once('.').ok_or(CursorError::Malformed)?;
+        let signature = unhex(signature).ok_or(CursorError::Malformed)?;
+        self.cursor_key
+            .mac(payload)
+            .verify_slice(&signature)
+            .map_err(|_| CursorError::InvalidSignature)?;
+        let bytes = unhex(payload).ok_or(CursorError::Malformed)?;
+        serde_json::from_slice(&bytes).map_err(|_| CursorError::Malformed)
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn unhex(s: &str) -> Option<Vec<u8>> {
+    if !s.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::TicketDraft;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn add(store: &mut TicketStore) -> TicketId {
+        store.add_ticket(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+    }
+
+    fn ids(page: &Page) -> Vec<TicketId> {
+        page.tickets.iter().map(|t| t.id).collect()
+    }
+
+    #[test]
+    fn pages_through_every_ticket() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..7).map(|_| add(&mut store)).collect();
+
+        let first = store.page(None, 3);
+        assert_eq!(ids(&first), all[0..3]);
+        let second = store.next_page(first.next.as_ref().unwrap(), 3).unwrap();
+        assert_eq!(ids(&second), all[3..6]);
+        let third = store.next_page(second.next.as_ref().unwrap(), 3).unwrap();
+        assert_eq!(ids(&third), all[6..]);
+        assert_eq!(third.next, None);
+
+        assert_eq!(ids(&store.page(Some(all[1]), 2)), all[2..4]);
+    }
+
+    #[test]
+    fn stable_under_concurrent_changes() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..4).map(|_| add(&mut store)).collect();
+        let cursor = store.page(None, 2).next.unwrap();
+
+        // Removing a ticket from the first page shifts nothing...
+        store.remove(all[0]);
+        // ...and new tickets show up at the end.
+        let new = add(&mut store);
+        let page = store.next_page(&cursor, 10).unwrap();
+        assert_eq!(ids(&page), [all[2], all[3], new]);
+    }
+
+    #[test]
+    fn backward_with_status_filter() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..6).map(|_| add(&mut store)).collect();
+        for &id in all.iter().step_by(2) {
+            store.transition(id, Status::InProgress).unwrap();
+        }
+        let options = PageOptions {
+            direction: Direction::Backward,
+            status: Some(Status::ToDo),
+        };
+
+        let first = store.page_with(None, 2, &options);
+        assert_eq!(ids(&first), [all[5], all[3]]);
+        let second = store.next_page(first.next.as_ref().unwrap(), 2).unwrap();
+        assert_eq!(ids(&second), [all[1]]);
+        assert_eq!(second.next, None);
+    }
+
+    #[test]
+    fn rejects_tampered_cursors() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..4).map(|_| add(&mut store)).collect();
+        let cursor = store.page(None, 1).next.unwrap().to_string();
+
+        // Point the cursor further ahead, keeping the signature.
+        let (_, signature) = cursor.split_once('.').unwrap();
+        let forged = Position {
+            after: Some(all[2]),
+            direction: Direction::Forward,
+            status: None,
+        };
+        let forged = format!("{}.{signature}", hex(&serde_json::to_vec(&forged).unwrap()));
+        assert_eq!(
+            store.next_page(&forged.parse().unwrap(), 1),
+            Err(CursorError::InvalidSignature)
+        );
+        assert_eq!(
+            store.next_page(&"not a cursor".parse().unwrap(), 1),
+            Err(CursorError::Malformed)
+        );
+
+        // Cursors are only valid for the store that issued them.
+        let mut other = TicketStore::new();
+        add(&mut other);
+        assert_eq!(
+            other.next_page(&cursor.parse().unwrap(), 1),
+            Err(CursorError::InvalidSignature)
+        );
+        assert!(store.next_page(&cursor.parse().unwrap(), 1).is_ok());
+
+        // Unless they share the key.
+        let key = CursorKey::new(*b"0123456789abcdef0123456789abcdef");
+        let mut store = TicketStore::new().with_cursor_key(key.clone());
+        add(&mut store);
+        add(&mut store);
+        let cursor = store.page(None, 1).next.unwrap();
+        let mut other = TicketStore::new().with_cursor_key(key);
+        add(&mut other);
+        add(&mut other);
+        assert!(other.next_page(&cursor, 1).is_ok());
+    }
+
+    #[test]
+    fn zero_limit_keeps_the_position() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..3).map(|_| add(&mut store)).collect();
+
+        let empty = store.page(None, 0);
+        assert!(empty.tickets.is_empty());
+        let cursor = empty.next.unwrap();
+        assert_eq!(ids(&store.next_page(&cursor, 2).unwrap()), all[0..2]);
+
+        let cursor = store.page(None, 1).next.unwrap();
+        let same = store.next_page(&cursor, 0).unwrap();
+        assert!(same.tickets.is_empty());
+        assert_eq!(same.next, Some(cursor));
+
+        // Past the last ticket, there's no next page to point to.
+        assert_eq!(store.page(Some(all[2]), 0).next, None);
+    }
+
+    #[test]
+    fn unbounded_limit() {
+        let mut store = TicketStore::new();
+        let all: Vec<TicketId> = (0..3).map(|_| add(&mut store)).collect();
+
+        let page = store.page(None, usize::MAX);
+        assert_eq!(ids(&page), all);
+        assert_eq!(page.next, None);
+    }
+}
@@ -0,0 +1,871 @@
+//! A small query language for filtering tickets.
+//!
+//! A query is a whitespace-separated list of clauses, all of which must match:
+//!
+//! - `status:inprogress`, `id:42`, `id>=100` (also `<`, `<=`, `>`);
+//!   `status` takes any state of the workflow, and `id` clauses compare
+//!   the number of the id, ignoring its project key
+//! - `title:"Login bug"` matches the whole title, `title:~crash` any part of it
+//!   (the same goes for `description`); text matching ignores case
+//! - a bare word or `"quoted text"` matches any part of the title or description
+//! - a leading `-` negates a clause: `-status:done`
+//! - `sort:title` or `sort:id:desc` orders the results (by `TicketId` otherwise);
+//!   statuses sort in the order of the workflow
+//! - `limit:10` caps the number of results
+//!
+//! ```
+//! use btreemap::Query;
+//!
+//! let query: Query = r#"status:inprogress title:~"crash" id>=100 -status:done"#
+//!     .parse()
+//!     .unwrap();
+//! assert_eq!(query.filters.len(), 4);
+//! ```
+
+use crate::{Status, Ticket, Workflow};
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+/// A range of byte offsets into the query text.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}..{}", self.start, self.end)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("{kind} at {span}")]
+pub struct QueryError {
+    pub kind: QueryErrorKind,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum QueryErrorKind {
+    #[error("Unexpected character `{0}`")]
+    UnexpectedCharacter(char),
+    #[error("Unterminated string")]
+    UnterminatedString,
+    #[error("Expected a field name or some text")]
+    ExpectedClause,
+    #[error("Expected a value")]
+    ExpectedValue,
+    #[error("Unknown field `{0}`. Use one of: id, status, title, description, sort, limit")]
+    UnknownField(String),
+    #[error("`{operator}` cannot be used with `{field}`")]
+    UnsupportedOperator {
+        field: &'static str,
+        operator: &'static str,
+    },
+    #[error("`{value}` is not a valid status. Use one of: {}", .states.join(", "))]
+    InvalidStatus { value: String, states: Vec<String> },
+    #[error("`{0}` is not a valid number")]
+    InvalidNumber(String),
+    #[error("Cannot sort by `{0}`. Use one of: id, title, status")]
+    InvalidSortKey(String),
+    #[error("`{0}` is not a sort direction. Use one of: asc, desc")]
+    InvalidSortDirection(String),
+    #[error("`{0}` cannot be negated")]
+    NegatedModifier(&'static str),
+    #[error("`{0}` can only be given once")]
+    DuplicateModifier(&'static str),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Default)]
+pub struct Query {
+    pub filters: Vec<Filter>,
+    pub sort: Option<Sort>,
+    pub limit: Option<usize>,
+    /// The workflow that statuses were resolved against, which also orders them.
+    workflow: Workflow,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Filter {
+    pub negated: bool,
+    pub predicate: Predicate,
+    pub span: Span,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Predicate {
+    Id(Comparison, u64),
+    Status(Status),
+    Title(TextMatch),
+    Description(TextMatch),
+    /// Matches any part of the title or the description.
+    Text(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextMatch {
+    Exact(String),
+    Contains(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Sort {
+    pub key: SortKey,
+    pub descending: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SortKey {
+    Id,
+    Title,
+    Status,
+}
+
+impl Query {
+    /// Parses a query whose statuses are those of the default workflow.
+    pub fn parse(input: &str) -> Result<Self, QueryError> {
+        Self::parse_with(input, &Workflow::default())
+    }
+
+    /// Parses a query whose statuses are those of `workflow`,
+    /// e.g. the [`TicketStore::workflow`](crate::TicketStore::workflow) it will run on.
+    pub fn parse_with(input: &str, workflow: &Workflow) -> Result<Self, QueryError> {
+        let tokens = tokenize(input)?;
+        Parser {
+            tokens,
+            position: 0,
+            end: input.len(),
+            workflow,
+        }
+        .parse()
+    }
+
+    /// Returns `true` if `ticket` passes every filter. Sorting and limits are ignored.
+    pub fn matches(&self, ticket: &Ticket) -> bool {
+        self.filters
+            .iter()
+            .all(|filter| filter.predicate.matches(ticket) != filter.negated)
+    }
+
+    /// Runs the query over `tickets`, e.g. a `&TicketStore`.
+    pub fn run<'a, I>(&self, tickets: I) -> Vec<&'a Ticket>
+    where
+        I: IntoIterator<Item = &'a Ticket>,
+    {
+        let mut results: Vec<&Ticket> = tickets
+            .into_iter()
+            .filter(|ticket| self.matches(ticket))
+            .collect();
+        if let Some(sort) = self.sort {
+            let positions: HashMap<&Status, usize> = self
+                .workflow
+                .states()
+                .enumerate()
+                .map(|(position, status)| (status, position))
+                .collect();
+            results.sort_by(|a, b| {
+                let ordering = sort.key.compare(a, b, &positions);
+                let ordering = if sort.descending {
+                    ordering.reverse()
+                } else {
+                    ordering
+                };
+                // Ties are always broken by ascending `TicketId`.
+                ordering.then(a.id.cmp(&b.id))
+            });
+        }
+        if let Some(limit) = self.limit {
+            results.truncate(limit);
+        }
+        results
+    }
+}
+
+impl FromStr for Query {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Query::parse(s)
+    }
+}
+
+impl Predicate {
+    fn matches(&self, ticket: &Ticket) -> bool {
+        match self {
+            Predicate::Id(comparison, value) => comparison.holds(ticket.id.number().cmp(value)),
+            Predicate::Status(status) => ticket.status == *status,
+            Predicate::Title(text) => text.matches(ticket.title.as_ref()),
+            Predicate::Description(text) => text.matches(ticket.description.as_ref()),
+            Predicate::Text(text) => {
+                let text = TextMatch::Contains(text.clone());
+                text.matches(ticket.title.as_ref()) || text.matches(ticket.description.as_ref())
+            }
+        }
+    }
+}
+
+impl Comparison {
+    fn holds(self, ordering: Ordering) -> bool {
+        match self {
+            Comparison::Eq => ordering.is_eq(),
+            Comparison::Lt => ordering.is_lt(),
+            Comparison::Le => ordering.is_le(),
+            Comparison::Gt => ordering.is_gt(),
+            Comparison::Ge => ordering.is_ge(),
+        }
+    }
+}
+
+impl TextMatch {
+    fn matches(&self, haystack: &str) -> bool {
+        let haystack = haystack.to_lowercase();
+        match self {
+            TextMatch::Exact(text) => haystack == text.to_lowercase(),
+            TextMatch::Contains(text) => haystack.contains(&text.to_lowercase()),
+        }
+    }
+}
+
+impl SortKey {
+    /// Compares two tickets. `positions` maps statuses to their place in the workflow.
+    fn compare(self, a: &Ticket, b: &Ticket, positions: &HashMap<&Status, usize>) -> Ordering {
+        match self {
+            SortKey::Id => a.id.cmp(&b.id),
+            SortKey::Title => a.title.cmp(&b.title),
+            SortKey::Status => {
+                // Statuses outside the workflow come last.
+                let position =
+                    |ticket: &Ticket| positions.get(&ticket.status).copied().unwrap_or(usize::MAX);
+                position(a).cmp(&position(b))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum TokenKind {
+    Word(String),
+    Quoted(String),
+    Minus,
+    Colon,
+    Tilde,
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl TokenKind {
+    fn describe(&self) -> &'static str {
+        match self {
+            TokenKind::Word(_) | TokenKind::Quoted(_) => "value",
+            TokenKind::Minus => "-",
+            TokenKind::Colon => ":",
+            TokenKind::Tilde => ":~",
+            TokenKind::Eq => "=",
+            TokenKind::Lt => "<",
+            TokenKind::Le => "<=",
+            TokenKind::Gt => ">",
+            TokenKind::Ge => ">=",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Token {
+    kind: TokenKind,
+    span: Span,
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.')
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = Vec::new();
+    let mut chars = input.char_indices().peekable();
+    // A `-` negates a clause only at its start; inside a word it's part of the word.
+    let mut at_clause_start = true;
+
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            at_clause_start = true;
+            continue;
+        }
+        let kind = match c {
+            '-' if at_clause_start => TokenKind::Minus,
+            ':' if chars.next_if(|(_, c)| *c == '~').is_some() => TokenKind::Tilde,
+            ':' => TokenKind::Colon,
+            '=' => TokenKind::Eq,
+            '<' if chars.next_if(|(_, c)| *c == '=').is_some() => TokenKind::Le,
+            '<' => TokenKind::Lt,
+            '>' if chars.next_if(|(_, c)| *c == '=').is_some() => TokenKind::Ge,
+            '>' => TokenKind::Gt,
+            '"' => {
+                let unterminated = || QueryError {
+                    kind: QueryErrorKind::UnterminatedString,
+                    span: Span {
+                        start,
+                        end: input.len(),
+                    },
+                };
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some((_, '"')) => break,
+                        Some((_, '\\')) => match chars.next() {
+                            Some((_, escaped)) => text.push(escaped),
+                            None => return Err(unterminated()),
+                        },
+                        Some((_, c)) => text.push(c),
+                        None => return Err(unterminated()),
+                    }
+                }
+                TokenKind::Quoted(text)
+            }
+            c if is_word_char(c) => {
+                let mut word = c.to_string();
+                while let Some((_, c)) = chars.next_if(|(_, c)| is_word_char(*c)) {
+                    word.push(c);
+                }
+                TokenKind::Word(word)
+            }
+            c => {
+                return Err(QueryError {
+                    kind: QueryErrorKind::UnexpectedCharacter(c),
+                    span: Span {
+                        start,
+                        end: start + c.len_utf8(),
+                    },
+                })
+            }
+        };
+        let end = chars.peek().map_or(input.len(), |(i, _)| *i);
+        tokens.push(Token {
+            kind,
+            span: Span { start, end },
+        });
+        at_clause_start = false;
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    position: usize,
+    /// The length of the input, used to point at a missing token.
+    end: usize,
+    workflow: &'a Workflow,
+}
+
+impl Parser<'_> {
+    fn parse(mut self) -> Result<Query, QueryError> {
+        let mut query = Query {
+            workflow: self.workflow.clone(),
+            ..Query::default()
+        };
+        while self.position < self.tokens.len() {
+            self.clause(&mut query)?;
+        }
+        Ok(query)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn missing(&self) -> Span {
+        Span {
+            start: self.end,
+            end: self.end,
+        }
+    }
+
+    fn clause(&mut self, query: &mut Query) -> Result<(), QueryError> {
+        let negation = self
+            .peek()
+            .filter(|token| token.kind == TokenKind::Minus)
+            .map(|token| token.span);
+        if negation.is_some() {
+            self.position += 1;
+        }
+        let Some(first) = self.next() else {
+            return Err(error(QueryErrorKind::ExpectedClause, self.missing()));
+        };
+        let start = negation.unwrap_or(first.span).start;
+
+        let name = match first.kind {
+            TokenKind::Quoted(text) => {
+                return self.push_filter(query, negation, start, Predicate::Text(text))
+            }
+            TokenKind::Word(word) => word,
+            _ => return Err(error(QueryErrorKind::ExpectedClause, first.span)),
+        };
+        let operator = match self.peek() {
+            Some(token)
+                if matches!(
+                    token.kind,
+                    TokenKind::Colon
+                        | TokenKind::Tilde
+                        | TokenKind::Eq
+                        | TokenKind::Lt
+                        | TokenKind::Le
+                        | TokenKind::Gt
+                        | TokenKind::Ge
+                ) =>
+            {
+                self.next().unwrap()
+            }
+            // A word on its own is free text.
+            _ => return self.push_filter(query, negation, start, Predicate::Text(name)),
+        };
+
+        let field = name.to_lowercase();
+        let predicate = match field.as_str() {
+            "id" => {
+                let comparison = match operator.kind {
+                    TokenKind::Colon | TokenKind::Eq => Comparison::Eq,
+                    TokenKind::Lt => Comparison::Lt,
+                    TokenKind::Le => Comparison::Le,
+                    TokenKind::Gt => Comparison::Gt,
+                    TokenKind::Ge => Comparison::Ge,
+                    _ => return Err(unsupported("id", &operator)),
+                };
+                let (value, span) = self.value()?;
+                let id = value
+                    .parse()
+                    .map_err(|_| error(QueryErrorKind::InvalidNumber(value), span))?;
+                Predicate::Id(comparison, id)
+            }
+            "status" => {
+                self.expect_colon("status", &operator)?;
+                let (value, span) = self.value()?;
+                let Some(status) = self.workflow.state(&value) else {
+                    let states = self.workflow.states().map(Status::to_string).collect();
+                    return Err(error(QueryErrorKind::InvalidStatus { value, states }, span));
+                };
+                Predicate::Status(status)
+            }
+            "title" => Predicate::Title(self.text_match("title", &operator)?),
+            "description" => Predicate::Description(self.text_match("description", &operator)?),
+            "sort" => {
+                self.modifier("sort", negation, query.sort.is_some(), first.span)?;
+                self.expect_colon("sort", &operator)?;
+                query.sort = Some(self.sort()?);
+                return Ok(());
+            }
+            "limit" => {
+                self.modifier("limit", negation, query.limit.is_some(), first.span)?;
+                self.expect_colon("limit", &operator)?;
+                let (value, span) = self.value()?;
+                let limit = value
+                    .parse()
+                    .map_err(|_| error(QueryErrorKind::InvalidNumber(value), span))?;
+                query.limit = Some(limit);
+                return Ok(());
+            }
+            _ => return Err(error(QueryErrorKind::UnknownField(name), first.span)),
+        };
+        self.push_filter(query, negation, start, predicate)
+    }
+
+    fn push_filter(
+        &self,
+        query: &mut Query,
+        negation: Option<Span>,
+        start: usize,
+        predicate: Predicate,
+    ) -> Result<(), QueryError> {
+        let end = self.tokens[self.position - 1].span.end;
+        query.filters.push(Filter {
+            negated: negation.is_some(),
+            predicate,
+            span: Span { start, end },
+        });
+        Ok(())
+    }
+
+    /// Checks that a `sort` or `limit` clause is neither negated nor repeated.
+    fn modifier(
+        &self,
+        name: &'static str,
+        negation: Option<Span>,
+        seen: bool,
+        span: Span,
+    ) -> Result<(), QueryError> {
+        if let Some(negation) = negation {
+            return Err(error(QueryErrorKind::NegatedModifier(name), negation));
+        }
+        if seen {
+            return Err(error(QueryErrorKind::DuplicateModifier(name), span));
+        }
+        Ok(())
+    }
+
+    fn expect_colon(&self, field: &'static str, operator: &Token) -> Result<(), QueryError> {
+        match operator.kind {
+            TokenKind::Colon => Ok(()),
+            _ => Err(unsupported(field, operator)),
+        }
+    }
+
+    fn value(&mut self) -> Result<(String, Span), QueryError> {
+        match self.next() {
+            Some(Token {
+                kind: TokenKind::Word(value) | TokenKind::Quoted(value),
+                span,
+            }) => Ok((value, span)),
+            Some(token) => Err(error(QueryErrorKind::ExpectedValue, token.span)),
+            None => Err(error(QueryErrorKind::ExpectedValue, self.missing())),
+        }
+    }
+
+    fn text_match(
+        &mut self,
+        field: &'static str,
+        operator: &Token,
+    ) -> Result<TextMatch, QueryError> {
+        match operator.kind {
+            TokenKind::Colon => Ok(TextMatch::Exact(self.value()?.0)),
+            TokenKind::Tilde => Ok(TextMatch::Contains(self.value()?.0)),
+            _ => Err(unsupported(field, operator)),
+        }
+    }
+
+    /// Parses `<key>` or `<key>:<direction>` after `sort:`.
+    fn sort(&mut self) -> Result<Sort, QueryError> {
+        let (key, key_span) = self.value()?;
+        let key = match key.to_lowercase().as_str() {
+            "id" => SortKey::Id,
+            "title" => SortKey::Title,
+            "status" => SortKey::Status,
+            _ => return Err(error(QueryErrorKind::InvalidSortKey(key), key_span)),
+        };
+        let mut descending = false;
+        if self
+            .peek()
+            .is_some_and(|token| token.kind == TokenKind::Colon)
+        {
+            self.position += 1;
+            let (direction, span) = self.value()?;
+            descending = match direction.to_lowercase().as_str() {
+                "asc" => false,
+                "desc" => true,
+                _ => return Err(error(QueryErrorKind::InvalidSortDirection(direction), span)),
+            };
+        }
+        Ok(Sort { key, descending })
+    }
+}
+
+fn error(kind: QueryErrorKind, span: Span) -> QueryError {
+    QueryError { kind, span }
+}
+
+fn unsupported(field: &'static str, operator: &Token) -> QueryError {
+    error(
+        QueryErrorKind::UnsupportedOperator {
+            field,
+            operator: operator.kind.describe(),
+        },
+        operator.span,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TicketDraft, TicketId, TicketPatch, TicketStore};
+    use ticket_fields::{TicketDescription, TicketTitle};
+
+    fn store() -> TicketStore {
+        let workflow = Workflow::default().allow(Status::ToDo, Status::Done);
+        let mut store = TicketStore::new().with_workflow(workflow);
+        let tickets = [
+            ("Crash on login", "The app crashes.", Status::InProgress),
+            ("Crash on logout", "Also crashes.", Status::InProgress),
+            ("Add dark mode", "Users asked for it.", Status::ToDo),
+            ("Fix typo", "In the login page.", Status::Done),
+        ];
+        for (title, description, status) in tickets {
+            let id = store.add_ticket(TicketDraft {
+                title: TicketTitle::try_from(title).unwrap(),
+                description: TicketDescription::try_from(description).unwrap(),
+            });
+            store
+                .patch(TicketPatch {
+                    id,
+                    title: None,
+                    description: None,
+                    status: Some(status),
+                })
+                .unwrap();
+        }
+        store
+    }
+
+    fn titles(query: &str, store: &TicketStore) -> Vec<String> {
+        let query: Query = query.parse().unwrap();
+        query
+            .run(store)
+            .into_iter()
+            .map(|t| t.title.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        let query =
+            Query::parse(r#"status:inprogress title:~"crash" id>=100 -status:done"#).unwrap();
+        assert_eq!(
+            query.filters,
+            [
+                Filter {
+                    negated: false,
+                    predicate: Predicate::Status(Status::InProgress),
+                    span: Span { start: 0, end: 17 },
+                },
+                Filter {
+                    negated: false,
+                    predicate: Predicate::Title(TextMatch::Contains("crash".into())),
+                    span: Span { start: 18, end: 32 },
+                },
+                Filter {
+                    negated: false,
+                    predicate: Predicate::Id(Comparison::Ge, 100),
+                    span: Span { start: 33, end: 40 },
+                },
+                Filter {
+                    negated: true,
+                    predicate: Predicate::Status(Status::Done),
+                    span: Span { start: 41, end: 53 },
+                },
+            ]
+        );
+        assert_eq!(query.sort, None);
+        assert_eq!(query.limit, None);
+    }
+
+    #[test]
+    fn test_free_text_before_negation() {
+        let query = Query::parse("crash -status:done -login").unwrap();
+        assert_eq!(
+            query.filters,
+            [
+                Filter {
+                    negated: false,
+                    predicate: Predicate::Text("crash".into()),
+                    span: Span { start: 0, end: 5 },
+                },
+                Filter {
+                    negated: true,
+                    predicate: Predicate::Status(Status::Done),
+                    span: Span { start: 6, end: 18 },
+                },
+                Filter {
+                    negated: true,
+                    predicate: Predicate::Text("login".into()),
+                    span: Span { start: 19, end: 25 },
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_run() {
+        let store = store();
+        assert_eq!(
+            titles(
+                r#"status:inprogress title:~"crash" -description:~also"#,
+                &store
+            ),
+            ["Crash on login"]
+        );
+        assert_eq!(titles("login", &store), ["Crash on login", "Fix typo"]);
+        assert_eq!(titles("title:\"fix TYPO\"", &store), ["Fix typo"]);
+        let ids: Vec<u64> = (&store).into_iter().map(|t| t.id.number()).collect();
+        assert_eq!(
+            titles(&format!("-status:inprogress id<{}", ids[3]), &store),
+            ["Add dark mode"]
+        );
+        assert_eq!(
+            titles(&format!("-status:done id:{}", ids[1]), &store),
+            ["Crash on logout"]
+        );
+    }
+
+    #[test]
+    fn test_sort_and_limit() {
+        let store = store();
+        assert_eq!(
+            titles("sort:title limit:2", &store),
+            ["Add dark mode", "Crash on login"]
+        );
+        assert_eq!(titles("sort:id:desc limit:1", &store), ["Fix typo"]);
+        assert_eq!(
+            titles("-status:todo sort:status:desc", &store),
+            ["Fix typo", "Crash on login", "Crash on logout"]
+        );
+    }
+
+    #[test]
+    fn test_runs_over_any_iterator() {
+        let store = store();
+        let query = Query::parse("status:inprogress").unwrap();
+        let tickets: Vec<&Ticket> = (&store)
+            .into_iter()
+            .filter(|t| t.id != TicketId::new(0))
+            .collect();
+        assert_eq!(query.run(tickets).len(), 1);
+    }
+
+    #[test]
+    fn test_custom_statuses() {
+        let workflow = Workflow::from_config(
+            r#"{
+                "states": ["Review"],
+                "transitions": [["ToDo", "InProgress"], ["InProgress", "Review"], ["Review", "Done"]]
+            }"#,
+        )
+        .unwrap();
+        let review = workflow.state("Review").unwrap();
+        let mut store = TicketStore::new().with_workflow(workflow);
+        let path = [Status::InProgress, review, Status::Done];
+        for (title, steps) in [("Done", 3), ("Review", 2), ("To do", 0)] {
+            let id = store.add_ticket(TicketDraft {
+                title: TicketTitle::try_from(title).unwrap(),
+                description: TicketDescription::try_from("A description").unwrap(),
+            });
+            for status in &path[..steps] {
+                store.transition(id, status.clone()).unwrap();
+            }
+        }
+
+        let titles = |query: &str| -> Vec<String> {
+            let query = Query::parse_with(query, store.workflow()).unwrap();
+            query
+                .run(&store)
+                .iter()
+                .map(|t| t.title.to_string())
+                .collect()
+        };
+        assert_eq!(titles("status:review"), ["Review"]);
+        // Statuses sort in the order of the workflow, not of their declaration.
+        assert_eq!(titles("sort:status"), ["To do", "Review", "Done"]);
+        assert!(matches!(
+            Query::parse("status:review").unwrap_err().kind,
+            QueryErrorKind::InvalidStatus { .. }
+        ));
+    }
+
+    fn parse_error(query: &str) -> (QueryErrorKind, Span) {
+        let error = Query::parse(query).unwrap_err();
+        (error.kind, error.span)
+    }
+
+    #[test]
+    fn test_error_spans() {
+        assert_eq!(
+            parse_error("status:open"),
+            (
+                QueryErrorKind::InvalidStatus {
+                    value: "open".into(),
+                    states: vec!["ToDo".into(), "InProgress".into(), "Done".into()]
+                },
+                Span { start: 7, end: 11 }
+            )
+        );
+        assert_eq!(
+            parse_error("title:~crash priority:high"),
+            (
+                QueryErrorKind::UnknownField("priority".into()),
+                Span { start: 13, end: 21 }
+            )
+        );
+        assert_eq!(
+            parse_error("status>=done"),
+            (
+                QueryErrorKind::UnsupportedOperator {
+                    field: "status",
+                    operator: ">="
+                },
+                Span { start: 6, end: 8 }
+            )
+        );
+        assert_eq!(
+            parse_error("title:\"crash"),
+            (
+                QueryErrorKind::UnterminatedString,
+                Span { start: 6, end: 12 }
+            )
+        );
+        // The backslash escapes the closing quote.
+        assert_eq!(
+            parse_error(r#"title:"crash\""#),
+            (
+                QueryErrorKind::UnterminatedString,
+                Span { start: 6, end: 14 }
+            )
+        );
+        assert_eq!(
+            parse_error(r#"title:"crash\"#),
+            (
+                QueryErrorKind::UnterminatedString,
+                Span { start: 6, end: 13 }
+            )
+        );
+        assert_eq!(
+            parse_error("id>="),
+            (QueryErrorKind::ExpectedValue, Span { start: 4, end: 4 })
+        );
+        assert_eq!(
+            parse_error("id:ten"),
+            (
+                QueryErrorKind::InvalidNumber("ten".into()),
+                Span { start: 3, end: 6 }
+            )
+        );
+        assert_eq!(
+            parse_error("-limit:3"),
+            (
+                QueryErrorKind::NegatedModifier("limit"),
+                Span { start: 0, end: 1 }
+            )
+        );
+        assert_eq!(
+            parse_error("crash & burn"),
+            (
+                QueryErrorKind::UnexpectedCharacter('&'),
+                Span { start: 6, end: 7 }
+            )
+        );
+    }
+
+    #[test]
+    fn test_error_message() {
+        let error = Query::parse("status:open").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "`open` is not a valid status. Use one of: ToDo, InProgress, Done at 7..11"
+        );
+    }
+}
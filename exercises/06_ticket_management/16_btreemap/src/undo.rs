@@ -0,0 +1,419 @@
+//! Undo and redo for `TicketStore` mutations.
+//!
+//! Every mutation made through `add_ticket`, `patch`, `transition` and `remove`
+//! is kept as a [`TicketCommand`], which knows how to invert itself.
+//! Undoing and redoing are mutations too: they are recorded in the store's history.
+
+use crate::{
+    FieldChange, Status, Ticket, TicketEventKind, TicketId, TicketLink, TicketPatch, TicketStore,
+};
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+
+/// An invertible mutation of a `TicketStore`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TicketCommand {
+    Insert(Ticket),
+    Delete(Ticket),
+    /// `before` holds the previous values of the fields set in `after`.
+    Patch {
+        before: TicketPatch,
+        after: TicketPatch,
+    },
+    Transition {
+        id: TicketId,
+        from: Status,
+        to: Status,
+    },
+    Link(TicketLink),
+    Unlink(TicketLink),
+}
+
+impl TicketCommand {
+    /// The command that undoes this one.
+    pub fn inverse(&self) -> TicketCommand {
+        match self {
+            TicketCommand::Insert(ticket) => TicketCommand::Delete(ticket.clone()),
+            TicketCommand::Delete(ticket) => TicketCommand::Insert(ticket.clone()),
+            TicketCommand::Patch { before, after } => TicketCommand::Patch {
+                before: after.clone(),
+                after: before.clone(),
+            },
+            TicketCommand::Transition { id, from, to } => TicketCommand::Transition {
+                id: *id,
+                from: to.clone(),
+                to: from.clone(),
+            },
+            TicketCommand::Link(link) => TicketCommand::Unlink(*link),
+            TicketCommand::Unlink(link) => TicketCommand::Link(*link),
+        }
+    }
+
+    pub fn ticket_id(&self) -> TicketId {
+        match self {
+            TicketCommand::Insert(ticket) | TicketCommand::Delete(ticket) => ticket.id,
+            TicketCommand::Patch { after, .. } => after.id,
+            TicketCommand::Transition { id, .. } => *id,
+            TicketCommand::Link(link) | TicketCommand::Unlink(link) => link.dependent(),
+        }
+    }
+
+    fn event(&self) -> TicketEventKind {
+        match self {
+            TicketCommand::Insert(ticket) => TicketEventKind::Created(ticket.clone()),
+            TicketCommand::Delete(_) => TicketEventKind::Removed,
+            TicketCommand::Patch { before, after } => {
+                TicketEventKind::Patched(FieldChange::between(before, after))
+            }
+            TicketCommand::Transition { from, to, .. } => TicketEventKind::Transitioned {
+                from: from.clone(),
+                to: to.clone(),
+            },
+            TicketCommand::Link(link) => TicketEventKind::Linked(*link),
+            TicketCommand::Unlink(link) => TicketEventKind::Unlinked(*link),
+        }
+    }
+}
+
+/// The patch that restores the fields of `ticket` that `patch` overwrites.
+pub(crate) fn revert(ticket: &Ticket, patch: &TicketPatch) -> TicketPatch {
+    TicketPatch {
+        id: ticket.id,
+        title: patch.title.as_ref().map(|_| ticket.title.clone()),
+        description: patch
+            .description
+            .as_ref()
+            .map(|_| ticket.description.clone()),
+        status: patch.status.as_ref().map(|_| ticket.status.clone()),
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct UndoLog {
+    /// Groups of commands that can be undone, oldest first.
+    done: VecDeque<Vec<TicketCommand>>,
+    /// Groups of commands that can be redone, most recently undone last.
+    undone: Vec<Vec<TicketCommand>>,
+    /// The commands of the transaction in progress, if any.
+    transaction: Option<Vec<TicketCommand>>,
+    depth: usize,
+}
+
+impl Default for UndoLog {
+    fn default() -> Self {
+        Self {
+            done: VecDeque::new(),
+            undone: Vec::new(),
+            transaction: None,
+            depth: TicketStore::DEFAULT_UNDO_DEPTH,
+        }
+    }
+}
+
+impl UndoLog {
+    fn push(&mut self, command: TicketCommand) {
+        self.undone.clear();
+        match &mut self.transaction {
+            Some(commands) => commands.push(command),
+            None => self.push_group(vec![command]),
+        }
+    }
+
+    fn push_group(&mut self, group: Vec<TicketCommand>) {
+        self.done.push_back(group);
+        while self.done.len() > self.depth {
+            self.done.pop_front();
+        }
+    }
+}
+
+impl TicketStore {
+    /// How many operations can be undone, by default.
+    pub const DEFAULT_UNDO_DEPTH: usize = 100;
+
+    /// Keeps at most `depth` operations to undo. Older ones are forgotten.
+    pub fn with_undo_depth(mut self, depth: usize) -> Self {
+        self.undo.depth = depth;
+        self
+    }
+
+    /// Records a mutation that has just been applied.
+    pub(crate) fn commit(&mut self, command: TicketCommand) {
+        self.history.record(command.ticket_id(), command.event());
+        self.undo.push(command);
+    }
+
+    /// Runs `f`, so that all the mutations it makes are undone and redone as one.
+    ///
+    /// Nested transactions are merged into the outermost one.
+    /// If `f` panics, the mutations it made before panicking are still undone as one.
+    pub fn transaction<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
+        if self.undo.transaction.is_some() {
+            return f(self);
+        }
+        self.reindex_stale();
+        self.undo.transaction = Some(Vec::new());
+        let output = panic::catch_unwind(AssertUnwindSafe(|| f(self)));
+        self.reindex_stale();
+        let commands = self.undo.transaction.take().unwrap_or_default();
+        if !commands.is_empty() {
+            self.undo.push_group(commands);
+        }
+        output.unwrap_or_else(|panic| panic::resume_unwind(panic))
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.undo.transaction.is_none() && !self.undo.done.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.undo.transaction.is_none() && !self.undo.undone.is_empty()
+    }
+
+    /// Reverts the most recent operation or transaction.
+    ///
+    /// Returns `false` if there is nothing to undo, a transaction is in progress,
+    /// or the operation changed a status in a way that the workflow doesn't allow.
+    pub fn undo(&mut self) -> bool {
+        self.reindex_stale();
+        let allowed = self
+            .undo
+            .done
+            .back()
+            .is_some_and(|group| self.follows_workflow(group));
+        if !self.can_undo() || !allowed {
+            return false;
+        }
+        let Some(group) = self.undo.done.pop_back() else {
+            return false;
+        };
+        for command in group.iter().rev() {
+            self.apply(command.inverse());
+        }
+        self.undo.undone.push(group);
+        true
+    }
+
+    /// Re-applies the most recently undone operation or transaction.
+    ///
+    /// Returns `false` if there is nothing to redo, a transaction is in progress,
+    /// or the operation changed a status in a way that the workflow doesn't allow.
+    pub fn redo(&mut self) -> bool {
+        self.reindex_stale();
+        let allowed = self
+            .undo
+            .undone
+            .last()
+            .is_some_and(|group| self.follows_workflow(group));
+        if !self.can_redo() || !allowed {
+            return false;
+        }
+        let Some(group) = self.undo.undone.pop() else {
+            return false;
+        };
+        for command in &group {
+            self.apply(command.clone());
+        }
+        self.undo.push_group(group);
+        true
+    }
+
+    /// Whether every status that `group` sets, when done or undone, fits the workflow.
+    ///
+    /// Undoing a status change takes it back rather than making a new one,
+    /// so it's enough for the change itself to follow a transition.
+    fn follows_workflow(&self, group: &[TicketCommand]) -> bool {
+        group.iter().all(|command| match command {
+            TicketCommand::Insert(ticket) | TicketCommand::Delete(ticket) => {
+                self.workflow.declares(&ticket.status)
+            }
+            TicketCommand::Patch { before, after } => match (&before.status, &after.status) {
+                (Some(from), Some(to)) => self.workflow.permits(from, to),
+                _ => true,
+            },
+            TicketCommand::Transition { from, to, .. } => self.workflow.permits(from, to),
+            TicketCommand::Link(_) | TicketCommand::Unlink(_) => true,
+        })
+    }
+
+    /// Applies `command` and records it in the history, but not in the undo log.
+    fn apply(&mut self, command: TicketCommand) {
+        self.history.record(command.ticket_id(), command.event());
+        match command {
+            TicketCommand::Insert(ticket) => self.insert_ticket(ticket),
+            TicketCommand::Delete(ticket) => {
+                self.remove_ticket(ticket.id);
+            }
+            TicketCommand::Patch { after, .. } => {
+                self.apply_patch(after);
+            }
+            TicketCommand::Transition { id, to, .. } => {
+                self.apply_patch(TicketPatch {
+                    id,
+                    title: None,
+                    description: None,
+                    status: Some(to),
+                });
+            }
+            TicketCommand::Link(link) => {
+                self.links.insert(link);
+            }
+            TicketCommand::Unlink(link) => {
+                self.links.remove(link);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Status, Ticket, TicketDraft, TicketId, TicketPatch, TicketStore, Workflow};
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+    use ticket_fields::TicketTitle;
+
+    fn draft() -> TicketDraft {
+        TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        }
+    }
+
+    fn retitle(id: TicketId, title: &str) -> TicketPatch {
+        TicketPatch {
+            id,
+            title: Some(TicketTitle::try_from(title).unwrap()),
+            description: None,
+            status: None,
+        }
+    }
+
+    fn snapshot(store: &TicketStore) -> Vec<Ticket> {
+        store.into_iter().cloned().collect()
+    }
+
+    #[test]
+    fn undo_and_redo_each_operation() {
+        let mut store = TicketStore::new();
+        let mut states = vec![snapshot(&store)];
+
+        let id = store.add_ticket(draft());
+        states.push(snapshot(&store));
+        store.patch(retitle(id, "Renamed")).unwrap();
+        states.push(snapshot(&store));
+        store.transition(id, Status::InProgress).unwrap();
+        states.push(snapshot(&store));
+        store.remove(id);
+        states.push(snapshot(&store));
+
+        for state in states.iter().rev().skip(1) {
+            assert!(store.undo());
+            assert_eq!(&snapshot(&store), state);
+        }
+        assert!(!store.undo());
+
+        for state in states.iter().skip(1) {
+            assert!(store.redo());
+            assert_eq!(&snapshot(&store), state);
+        }
+        assert!(!store.redo());
+    }
+
+    #[test]
+    fn undo_keeps_indexes_and_history_in_sync() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        store.transition(id, Status::InProgress).unwrap();
+        store.undo();
+
+        assert_eq!(store.by_status(Status::ToDo).len(), 1);
+        assert!(store.by_status(Status::InProgress).is_empty());
+        assert_eq!(store.history(id).count(), 3);
+        let replayed = TicketStore::replay(Workflow::default(), store.events()).unwrap();
+        assert_eq!(snapshot(&replayed), snapshot(&store));
+    }
+
+    #[test]
+    fn undo_and_redo_follow_the_workflow() {
+        let mut store = TicketStore::new().with_workflow(Workflow::default().with_reopen());
+        let id = store.add_ticket(draft());
+        for status in [Status::InProgress, Status::Done, Status::ToDo] {
+            store.transition(id, status).unwrap();
+        }
+        // Taking a transition back doesn't need a transition going back.
+        assert!(store.undo());
+        assert_eq!(store[id].status, Status::Done);
+
+        // Without reopening, the reopening can't be redone...
+        let mut store = store.with_workflow(Workflow::default());
+        assert!(!store.redo());
+        assert_eq!(store[id].status, Status::Done);
+        // ...but the other transitions can still be undone.
+        assert!(store.undo());
+        assert_eq!(store[id].status, Status::InProgress);
+    }
+
+    #[test]
+    fn transactions_undo_as_a_unit() {
+        let mut store = TicketStore::new();
+        let first = store.add_ticket(draft());
+        let before = snapshot(&store);
+
+        let second = store.transaction(|store| {
+            store.patch(retitle(first, "Renamed")).unwrap();
+            let second = store.transaction(|store| store.add_ticket(draft()));
+            store.transition(second, Status::InProgress).unwrap();
+            assert!(!store.undo());
+            second
+        });
+        let after = snapshot(&store);
+
+        assert!(store.undo());
+        assert_eq!(snapshot(&store), before);
+        assert!(store.get(second).is_none());
+        assert!(store.redo());
+        assert_eq!(snapshot(&store), after);
+    }
+
+    #[test]
+    fn a_panicking_transaction_ends() {
+        let mut store = TicketStore::new();
+        let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            store.transaction(|store| {
+                store.add_ticket(draft());
+                panic!("the transaction failed halfway");
+            })
+        }));
+        assert!(result.is_err());
+
+        assert!(store.can_undo());
+        assert!(store.undo());
+        assert!(snapshot(&store).is_empty());
+    }
+
+    #[test]
+    fn new_mutations_clear_redo() {
+        let mut store = TicketStore::new();
+        let id = store.add_ticket(draft());
+        store.patch(retitle(id, "Renamed")).unwrap();
+        store.undo();
+        assert!(store.can_redo());
+
+        store.patch(retitle(id, "Renamed again")).unwrap();
+        assert!(!store.can_redo());
+        assert!(!store.redo());
+        assert_eq!(store[id].title.as_ref(), "Renamed again");
+    }
+
+    #[test]
+    fn depth_is_bounded() {
+        let mut store = TicketStore::new().with_undo_depth(2);
+        let id = store.add_ticket(draft());
+        for title in ["One", "Two", "Three"] {
+            store.patch(retitle(id, title)).unwrap();
+        }
+        assert!(store.undo());
+        assert!(store.undo());
+        assert!(!store.undo());
+        assert_eq!(store[id].title.as_ref(), "One");
+    }
+}
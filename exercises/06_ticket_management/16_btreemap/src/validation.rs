@@ -0,0 +1,209 @@
+use crate::{ParseStatusError, Status, TicketDraft};
+use std::error::Error;
+use std::fmt;
+use ticket_fields::{
+    LengthUnit, TicketDescription, TicketDescriptionError, TicketTitle, TicketTitleError,
+};
+
+/// The validation error of a single field.
+#[derive(Debug, PartialEq, Eq)]
+pub struct FieldError {
+    /// The name of the field that failed validation.
+    pub field: &'static str,
+    /// The length of the offending input, measured the way its field's policy
+    /// measures it (in characters for the status).
+    pub length: usize,
+    pub kind: FieldErrorKind,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum FieldErrorKind {
+    Title(TicketTitleError),
+    Description(TicketDescriptionError),
+    Status(ParseStatusError),
+}
+
+impl fmt::Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Invalid {}: ", self.field)?;
+        match &self.kind {
+            FieldErrorKind::Title(e) => e.fmt(f),
+            FieldErrorKind::Description(e) => e.fmt(f),
+            FieldErrorKind::Status(e) => e.fmt(f),
+        }
+    }
+}
+
+impl Error for FieldError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            FieldErrorKind::Title(e) => Some(e),
+            FieldErrorKind::Description(e) => Some(e),
+            FieldErrorKind::Status(e) => Some(e),
+        }
+    }
+}
+
+/// Every field error found while validating a draft, in field order.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ValidationReport {
+    errors: Vec<FieldError>,
+}
+
+impl ValidationReport {
+    pub fn errors(&self) -> &[FieldError] {
+        &self.errors
+    }
+
+    /// Returns the error reported for `field`, if any.
+    pub fn field(&self, field: &str) -> Option<&FieldError> {
+        self.errors.iter().find(|e| e.field == field)
+    }
+}
+
+impl fmt::Display for ValidationReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The ticket draft is invalid")?;
+        for error in &self.errors {
+            write!(f, "\n- {}", error)?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for ValidationReport {
+    /// The first field error; the remaining ones are available via [`ValidationReport::errors`].
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.errors.first().map(|e| e as &(dyn Error + 'static))
+    }
+}
+
+impl TicketDraft {
+    /// Validates every field of a draft coming from untrusted input, such as a form.
+    ///
+    /// Unlike building the draft field by field, all invalid fields are reported at once.
+    /// The parsed status is returned alongside the draft.
+    pub fn validate_from(
+        raw_title: &str,
+        raw_description: &str,
+        raw_status: &str,
+    ) -> Result<(TicketDraft, Status), ValidationReport> {
+        let mut errors = Vec::new();
+        let title = match TicketTitle::try_from(raw_title) {
+            Ok(title) => Some(title),
+            Err(e) => {
+                errors.push(field_error(
+                    "title",
+                    raw_title,
+                    TicketTitle::DEFAULT_POLICY.unit,
+                    FieldErrorKind::Title(e),
+                ));
+                None
+            }
+        };
+        let description = match TicketDescription::try_from(raw_description) {
+            Ok(description) => Some(description),
+            Err(e) => {
+                let kind = FieldErrorKind::Description(e);
+                errors.push(field_error(
+                    "description",
+                    raw_description,
+                    TicketDescription::DEFAULT_POLICY.unit,
+                    kind,
+                ));
+                None
+            }
+        };
+        let status = match Status::try_from(raw_status) {
+            Ok(status) => Some(status),
+            Err(e) => {
+                errors.push(field_error(
+                    "status",
+                    raw_status,
+                    LengthUnit::Chars,
+                    FieldErrorKind::Status(e),
+                ));
+                None
+            }
+        };
+
+        match (title, description, status) {
+            (Some(title), Some(description), Some(status)) => {
+                Ok((TicketDraft { title, description }, status))
+            }
+            _ => Err(ValidationReport { errors }),
+        }
+    }
+}
+
+fn field_error(
+    field: &'static str,
+    raw: &str,
+    unit: LengthUnit,
+    kind: FieldErrorKind,
+) -> FieldError {
+    FieldError {
+        field,
+        length: unit.measure(raw),
+        kind,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use common::{overly_long_description, valid_description, valid_title};
+
+    #[test]
+    fn valid_draft() {
+        let (draft, status) =
+            TicketDraft::validate_from(&valid_title(), &valid_description(), "InProgress").unwrap();
+        assert_eq!(draft.title, TicketTitle::try_from(valid_title()).unwrap());
+        assert_eq!(status, Status::InProgress);
+    }
+
+    #[test]
+    fn reports_every_invalid_field() {
+        let report =
+            TicketDraft::validate_from("", &overly_long_description(), "Blocked").unwrap_err();
+        let fields: Vec<_> = report.errors().iter().map(|e| e.field).collect();
+        assert_eq!(fields, ["title", "description", "status"]);
+
+        let description = report.field("description").unwrap();
+        assert_eq!(
+            description.length,
+            overly_long_description().chars().count()
+        );
+        assert!(matches!(
+            description.kind,
+            FieldErrorKind::Description(TicketDescriptionError::TooLong { .. })
+        ));
+        assert_eq!(
+            report.field("status").unwrap().to_string(),
+            "Invalid status: `Blocked` is not a valid status. Use one of: ToDo, InProgress, Done"
+        );
+    }
+
+    #[test]
+    fn length_counts_characters() {
+        let title = "工单".repeat(26);
+        let report = TicketDraft::validate_from(&title, &valid_description(), "ToDo").unwrap_err();
+        assert_eq!(report.field("title").unwrap().length, 52);
+    }
+
+    #[test]
+    fn source_chains_to_field_error() {
+        let report = TicketDraft::validate_from("", &valid_description(), "done").unwrap_err();
+        let field_error = report.source().unwrap();
+        assert_eq!(
+            field_error.to_string(),
+            "Invalid title: The title cannot be empty"
+        );
+
+        let title_error = field_error.source().unwrap();
+        assert_eq!(
+            title_error.downcast_ref::<TicketTitleError>(),
+            Some(&TicketTitleError::Empty)
+        );
+    }
+}
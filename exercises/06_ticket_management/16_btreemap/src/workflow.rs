@@ -0,0 +1,259 @@
+//! The transitions a ticket's status is allowed to go through.
+
+use crate::{Status, TicketId};
+use serde::Deserialize;
+use std::collections::HashSet;
+
+/// A set of allowed status transitions.
+///
+/// The default workflow is `ToDo -> InProgress -> Done`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Workflow {
+    /// In the order they were declared, starting with the built-in ones.
+    states: Vec<Status>,
+    transitions: HashSet<(Status, Status)>,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TransitionError {
+    #[error("There is no ticket with id {0}")]
+    TicketNotFound(TicketId),
+    #[error("A ticket cannot move from {from} to {to}")]
+    InvalidTransition { from: Status, to: Status },
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WorkflowConfigError {
+    #[error("The workflow configuration is not valid JSON")]
+    Malformed(#[from] serde_json::Error),
+    #[error("`{0}` is declared more than once")]
+    DuplicateState(String),
+    #[error("The transition from `{from}` to `{to}` uses an undeclared state")]
+    UndeclaredState { from: String, to: String },
+}
+
+/// The on-disk form of a workflow, e.g.
+///
+/// ```json
+/// {
+///     "states": ["Review"],
+///     "transitions": [["ToDo", "InProgress"], ["InProgress", "Review"], ["Review", "Done"]]
+/// }
+/// ```
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct WorkflowConfig {
+    /// Custom states, on top of the built-in `ToDo`, `InProgress` and `Done`.
+    #[serde(default)]
+    states: Vec<String>,
+    transitions: Vec<(String, String)>,
+}
+
+impl Workflow {
+    /// A workflow with the built-in states and no transitions.
+    pub fn empty() -> Self {
+        Self {
+            states: vec![Status::ToDo, Status::InProgress, Status::Done],
+            transitions: HashSet::new(),
+        }
+    }
+
+    /// Allows tickets to move from `from` to `to`.
+    pub fn allow(mut self, from: Status, to: Status) -> Self {
+        self.declare(from.clone());
+        self.declare(to.clone());
+        self.transitions.insert((from, to));
+        self
+    }
+
+    /// Adds `state` to the workflow, returning `false` if it was already there.
+    fn declare(&mut self, state: Status) -> bool {
+        if self.states.contains(&state) {
+            return false;
+        }
+        self.states.push(state);
+        true
+    }
+
+    /// Allows done tickets to be reopened.
+    pub fn with_reopen(self) -> Self {
+        self.allow(Status::Done, Status::ToDo)
+    }
+
+    /// Loads a workflow from its JSON configuration.
+    ///
+    /// Built-in states are referred to by name (`ToDo`, `InProgress`, `Done`);
+    /// every other state must be listed in `states`.
+    pub fn from_config(json: &str) -> Result<Self, WorkflowConfigError> {
+        let config: WorkflowConfig = serde_json::from_str(json)?;
+        let mut workflow = Self::empty();
+        for name in config.states {
+            let state = Status::try_from(name.as_str()).unwrap_or(Status::Custom(name.clone()));
+            if !workflow.declare(state) {
+                return Err(WorkflowConfigError::DuplicateState(name));
+            }
+        }
+        for (from, to) in config.transitions {
+            match (workflow.state(&from), workflow.state(&to)) {
+                (Some(from), Some(to)) => workflow = workflow.allow(from, to),
+                _ => return Err(WorkflowConfigError::UndeclaredState { from, to }),
+            }
+        }
+        Ok(workflow)
+    }
+
+    /// Looks up a state of this workflow by name, ignoring case.
+    pub fn state(&self, name: &str) -> Option<Status> {
+        self.states
+            .iter()
+            .find(|state| state.to_string().eq_ignore_ascii_case(name))
+            .cloned()
+    }
+
+    /// The states in the order tickets go through them: by the fewest transitions
+    /// it takes to reach them from `ToDo`, then in the order they were declared.
+    /// States that can't be reached from `ToDo` come last.
+    pub fn states(&self) -> impl Iterator<Item = &Status> {
+        // `ToDo` is always declared first.
+        let mut ordered: Vec<&Status> = vec![&self.states[0]];
+        let mut next = 0;
+        while let Some(&from) = ordered.get(next) {
+            for state in &self.states {
+                if !ordered.contains(&state) && self.allows(from, state) {
+                    ordered.push(state);
+                }
+            }
+            next += 1;
+        }
+        for state in &self.states {
+            if !ordered.contains(&state) {
+                ordered.push(state);
+            }
+        }
+        ordered.into_iter()
+    }
+
+    /// Whether `status` is one of the states of this workflow.
+    pub fn declares(&self, status: &Status) -> bool {
+        self.states.contains(status)
+    }
+
+    pub fn allows(&self, from: &Status, to: &Status) -> bool {
+        self.transitions.contains(&(from.clone(), to.clone()))
+    }
+
+    /// Whether a patch may set the status of a ticket from `from` to `to`:
+    /// either it follows a transition, or it leaves the status as it is.
+    pub(crate) fn permits(&self, from: &Status, to: &Status) -> bool {
+        from == to || self.allows(from, to)
+    }
+}
+
+impl Default for Workflow {
+    fn default() -> Self {
+        Self::empty()
+            .allow(Status::ToDo, Status::InProgress)
+            .allow(Status::InProgress, Status::Done)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{TicketDraft, TicketStore};
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn add_ticket(store: &mut TicketStore) -> TicketId {
+        store.add_ticket(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+    }
+
+    #[test]
+    fn default_workflow() {
+        let mut store = TicketStore::new();
+        let id = add_ticket(&mut store);
+
+        assert_eq!(
+            store.transition(id, Status::Done).unwrap_err(),
+            TransitionError::InvalidTransition {
+                from: Status::ToDo,
+                to: Status::Done
+            }
+        );
+        store.transition(id, Status::InProgress).unwrap();
+        assert_eq!(
+            store.transition(id, Status::Done).unwrap().status,
+            Status::Done
+        );
+        assert!(store.transition(id, Status::ToDo).is_err());
+        assert_eq!(store.by_status(Status::Done).len(), 1);
+    }
+
+    #[test]
+    fn reopen() {
+        let workflow = Workflow::default().with_reopen();
+        let mut store = TicketStore::new().with_workflow(workflow);
+        let id = add_ticket(&mut store);
+        store.transition(id, Status::InProgress).unwrap();
+        store.transition(id, Status::Done).unwrap();
+        assert_eq!(
+            store.transition(id, Status::ToDo).unwrap().status,
+            Status::ToDo
+        );
+    }
+
+    #[test]
+    fn missing_ticket() {
+        let mut store = TicketStore::new();
+        let id = add_ticket(&mut store);
+        store.remove(id);
+        assert_eq!(
+            store.transition(id, Status::InProgress).unwrap_err(),
+            TransitionError::TicketNotFound(id)
+        );
+    }
+
+    #[test]
+    fn custom_states_from_config() {
+        let workflow = Workflow::from_config(
+            r#"{
+                "states": ["Review"],
+                "transitions": [["todo", "InProgress"], ["InProgress", "Review"], ["Review", "Done"]]
+            }"#,
+        )
+        .unwrap();
+        let review = workflow.state("review").unwrap();
+        assert_eq!(review, Status::Custom("Review".into()));
+        let states: Vec<&Status> = workflow.states().collect();
+        assert_eq!(
+            states,
+            [&Status::ToDo, &Status::InProgress, &review, &Status::Done]
+        );
+
+        let mut store = TicketStore::new().with_workflow(workflow);
+        let id = add_ticket(&mut store);
+        store.transition(id, Status::InProgress).unwrap();
+        let err = store.transition(id, Status::Done).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "A ticket cannot move from InProgress to Done"
+        );
+        store.transition(id, review.clone()).unwrap();
+        assert_eq!(store.by_status(review).len(), 1);
+        store.transition(id, Status::Done).unwrap();
+    }
+
+    #[test]
+    fn invalid_config() {
+        let err = Workflow::from_config(r#"{"transitions": [["ToDo", "Review"]]}"#).unwrap_err();
+        assert!(matches!(err, WorkflowConfigError::UndeclaredState { .. }));
+
+        let err = Workflow::from_config(r#"{"states": ["Done"], "transitions": []}"#).unwrap_err();
+        assert!(matches!(err, WorkflowConfigError::DuplicateState(_)));
+
+        let err = Workflow::from_config(r#"{"transitions": {}}"#).unwrap_err();
+        assert!(matches!(err, WorkflowConfigError::Malformed(_)));
+    }
+}
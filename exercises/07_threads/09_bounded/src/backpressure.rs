@@ -0,0 +1,302 @@
+//! What clients do when the server's command queue is full.
+
+use crate::{Command, OverloadedError};
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvError, SyncSender, TrySendError};
+use std::time::{Duration, Instant};
+
+/// How a client deals with a full command queue. It's chosen at launch time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Fail with [`OverloadedError`] right away.
+    #[default]
+    FailFast,
+    /// Wait for room in the queue, for at most `timeout`.
+    Block { timeout: Duration },
+    /// Try again up to `max_attempts` times in total, waiting a random delay between
+    /// zero and `base_delay * 2^n` after the `n`-th failed attempt.
+    Retry {
+        max_attempts: u32,
+        base_delay: Duration,
+    },
+    /// Give reads a queue of their own, with room for `read_capacity` commands,
+    /// which the server serves before any queued write. Both queues fail fast.
+    Prioritized { read_capacity: usize },
+}
+
+/// Counters shared by a client and all its clones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientMetrics {
+    pub policy: Backpressure,
+    /// The commands accepted by the server's queue.
+    pub sent: u64,
+    /// The calls that failed because the queue was full.
+    pub overloaded: u64,
+    /// The attempts made after the first one, under [`Backpressure::Retry`].
+    pub retries: u64,
+    /// The time spent waiting for room in the queue.
+    pub waited: Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    sent: AtomicU64,
+    overloaded: AtomicU64,
+    retries: AtomicU64,
+    waited_nanos: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn snapshot(&self, policy: Backpressure) -> ClientMetrics {
+        ClientMetrics {
+            policy,
+            sent: self.sent.load(Ordering::Relaxed),
+            overloaded: self.overloaded.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            waited: Duration::from_nanos(self.waited_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn wait(&self, duration: Duration) {
+        std::thread::sleep(duration);
+        self.waited_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// The sending side of the server's command queues.
+#[derive(Clone)]
+pub(crate) struct Outbox {
+    commands: SyncSender<Command>,
+    reads: Option<Priority>,
+}
+
+#[derive(Clone)]
+struct Priority {
+    commands: SyncSender<Command>,
+    /// Rung once per queued command, read or write, so that the server can block
+    /// until there's something in either queue.
+    doorbell: SyncSender<()>,
+}
+
+/// The receiving side of the server's command queues.
+pub(crate) struct Inbox {
+    commands: Receiver<Command>,
+    reads: Option<(Receiver<Command>, Receiver<()>)>,
+}
+
+/// Creates the command queues of the server, with room for `capacity` commands.
+pub(crate) fn queue(capacity: usize, policy: Backpressure) -> (Outbox, Inbox) {
+    let (sender, receiver) = sync_channel(capacity);
+    let Backpressure::Prioritized { read_capacity } = policy else {
+        let outbox = Outbox {
+            commands: sender,
+            reads: None,
+        };
+        let inbox = Inbox {
+            commands: receiver,
+            reads: None,
+        };
+        return (outbox, inbox);
+    };
+    let (read_sender, read_receiver) = sync_channel(read_capacity);
+    // There are never more rings pending than queued commands, so ringing never blocks.
+    let (doorbell, doorbell_receiver) = sync_channel(capacity + read_capacity);
+    let outbox = Outbox {
+        commands: sender,
+        reads: Some(Priority {
+            commands: read_sender,
+            doorbell,
+        }),
+    };
+    let inbox = Inbox {
+        commands: receiver,
+        reads: Some((read_receiver, doorbell_receiver)),
+    };
+    (outbox, inbox)
+}
+
+impl Outbox {
+    /// Queues `command`, dealing with a full queue according to `policy`.
+    pub(crate) fn send(
+        &self,
+        mut command: Command,
+        policy: Backpressure,
+        counters: &Counters,
+    ) -> Result<(), OverloadedError> {
+        let deadline = match policy {
+            Backpressure::Block { timeout } => Some(Instant::now() + timeout),
+            _ => None,
+        };
+        let mut attempts = 1;
+        loop {
+            match self.try_send(command) {
+                Ok(()) => {
+                    counters.sent.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(OverloadedError),
+                Err(TrySendError::Full(rejected)) => command = rejected,
+            }
+            let delay = match policy {
+                Backpressure::Block { .. } => deadline
+                    .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                    .filter(|remaining| !remaining.is_zero())
+                    .map(|remaining| remaining.min(POLL_INTERVAL)),
+                Backpressure::Retry {
+                    max_attempts,
+                    base_delay,
+                } if attempts < max_attempts => {
+                    counters.retries.fetch_add(1, Ordering::Relaxed);
+                    Some(jitter(
+                        base_delay.saturating_mul(1 << (attempts - 1).min(16)),
+                    ))
+                }
+                _ => None,
+            };
+            let Some(delay) = delay else {
+                counters.overloaded.fetch_add(1, Ordering::Relaxed);
+                return Err(OverloadedError);
+            };
+            counters.wait(delay);
+            attempts += 1;
+        }
+    }
+
+    fn try_send(&self, command: Command) -> Result<(), TrySendError<Command>> {
+        match &self.reads {
+            Some(reads) if command.is_read() => reads.commands.try_send(command)?,
+            _ => self.commands.try_send(command)?,
+        }
+        if let Some(reads) = &self.reads {
+            // Only fails if the server is gone, and then nobody is listening anyway.
+            let _ = reads.doorbell.send(());
+        }
+        Ok(())
+    }
+}
+
+impl Inbox {
+    /// Waits for the next command. Queued reads come before queued writes.
+    pub(crate) fn recv(&self) -> Result<Command, RecvError> {
+        let Some((reads, doorbell)) = &self.reads else {
+            return self.commands.recv();
+        };
+        doorbell.recv()?;
+        // Commands are queued before the doorbell rings.
+        Ok(reads
+            .try_recv()
+            .or_else(|_| self.commands.try_recv())
+            .expect("every ring of the doorbell is preceded by a queued command"))
+    }
+}
+
+/// How often a blocked client checks whether the queue has room again.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A random duration between zero and `max`.
+fn jitter(max: Duration) -> Duration {
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::TicketDraft;
+    use crate::store::TicketStore;
+    use std::thread::spawn;
+    use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+    fn insert() -> Command {
+        let (response_channel, _) = sync_channel(1);
+        Command::Insert {
+            draft: TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            },
+            response_channel,
+        }
+    }
+
+    fn get() -> Command {
+        let (response_channel, _) = sync_channel(1);
+        let id = TicketStore::new().add_ticket(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        });
+        Command::Get {
+            id,
+            response_channel,
+        }
+    }
+
+    #[test]
+    fn fail_fast() {
+        let policy = Backpressure::FailFast;
+        let (outbox, _inbox) = queue(1, policy);
+        let counters = Counters::default();
+        outbox.send(insert(), policy, &counters).unwrap();
+        assert!(outbox.send(insert(), policy, &counters).is_err());
+
+        let metrics = counters.snapshot(policy);
+        assert_eq!(metrics.sent, 1);
+        assert_eq!(metrics.overloaded, 1);
+        assert_eq!(metrics.retries, 0);
+        assert_eq!(metrics.waited, Duration::ZERO);
+    }
+
+    #[test]
+    fn retry_with_backoff() {
+        let policy = Backpressure::Retry {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+        };
+        let (outbox, _inbox) = queue(1, policy);
+        let counters = Counters::default();
+        outbox.send(insert(), policy, &counters).unwrap();
+        assert!(outbox.send(insert(), policy, &counters).is_err());
+
+        let metrics = counters.snapshot(policy);
+        assert_eq!(metrics.overloaded, 1);
+        assert_eq!(metrics.retries, 2);
+    }
+
+    #[test]
+    fn block_until_deadline() {
+        let policy = Backpressure::Block {
+            timeout: Duration::from_millis(5),
+        };
+        let (outbox, inbox) = queue(1, policy);
+        let counters = Counters::default();
+        outbox.send(insert(), policy, &counters).unwrap();
+        assert!(outbox.send(insert(), policy, &counters).is_err());
+        assert!(counters.snapshot(policy).waited > Duration::ZERO);
+
+        // With enough patience, the call goes through once the server catches up.
+        let policy = Backpressure::Block {
+            timeout: Duration::from_secs(30),
+        };
+        let server = spawn(move || {
+            std::thread::sleep(Duration::from_millis(10));
+            inbox.recv().unwrap();
+            inbox
+        });
+        outbox.send(insert(), policy, &counters).unwrap();
+        server.join().unwrap();
+        assert_eq!(counters.snapshot(policy).overloaded, 1);
+    }
+
+    #[test]
+    fn reads_bypass_queued_writes() {
+        let policy = Backpressure::Prioritized { read_capacity: 1 };
+        let (outbox, inbox) = queue(1, policy);
+        let counters = Counters::default();
+        outbox.send(insert(), policy, &counters).unwrap();
+        assert!(outbox.send(insert(), policy, &counters).is_err());
+        outbox.send(get(), policy, &counters).unwrap();
+
+        assert!(matches!(inbox.recv(), Ok(Command::Get { .. })));
+        assert!(matches!(inbox.recv(), Ok(Command::Insert { .. })));
+    }
+}
@@ -1,44 +1,92 @@
-// TODO: Convert the implementation to use bounded channels.
+use crate::backpressure::{Counters, Inbox, Outbox};
 use crate::data::{Ticket, TicketDraft};
 use crate::store::{TicketId, TicketStore};
-use std::sync::mpsc::{Receiver, Sender};
+use std::sync::mpsc::{sync_channel, SyncSender};
+use std::sync::Arc;
 
+pub mod backpressure;
 pub mod data;
 pub mod store;
 
+pub use backpressure::{Backpressure, ClientMetrics};
+
 #[derive(Clone)]
 pub struct TicketStoreClient {
-    sender: todo!(),
+    sender: Outbox,
+    policy: Backpressure,
+    counters: Arc<Counters>,
 }
 
 impl TicketStoreClient {
-    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, todo!()> {
-        todo!()
+    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, OverloadedError> {
+        let (response_sender, response_receiver) = sync_channel(1);
+        self.sender.send(
+            Command::Insert {
+                draft,
+                response_channel: response_sender,
+            },
+            self.policy,
+            &self.counters,
+        )?;
+        Ok(response_receiver.recv().unwrap())
+    }
+
+    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, OverloadedError> {
+        let (response_sender, response_receiver) = sync_channel(1);
+        self.sender.send(
+            Command::Get {
+                id,
+                response_channel: response_sender,
+            },
+            self.policy,
+            &self.counters,
+        )?;
+        Ok(response_receiver.recv().unwrap())
     }
 
-    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, todo!()> {
-        todo!()
+    pub fn metrics(&self) -> ClientMetrics {
+        self.counters.snapshot(self.policy)
     }
 }
 
+#[derive(Debug, thiserror::Error)]
+#[error("The store is overloaded")]
+pub struct OverloadedError;
+
 pub fn launch(capacity: usize) -> TicketStoreClient {
-    todo!();
+    launch_with(capacity, Backpressure::default())
+}
+
+/// Like [`launch`], with the given policy for when the command queue is full.
+pub fn launch_with(capacity: usize, policy: Backpressure) -> TicketStoreClient {
+    let (sender, receiver) = backpressure::queue(capacity, policy);
     std::thread::spawn(move || server(receiver));
-    todo!()
+    TicketStoreClient {
+        sender,
+        policy,
+        counters: Arc::default(),
+    }
 }
 
 enum Command {
     Insert {
         draft: TicketDraft,
-        response_channel: todo!(),
+        response_channel: SyncSender<TicketId>,
     },
     Get {
         id: TicketId,
-        response_channel: todo!(),
+        response_channel: SyncSender<Option<Ticket>>,
     },
 }
 
-pub fn server(receiver: Receiver<Command>) {
+impl Command {
+    /// Whether the command leaves the store unchanged.
+    fn is_read(&self) -> bool {
+        matches!(self, Command::Get { .. })
+    }
+}
+
+fn server(receiver: Inbox) {
     let mut store = TicketStore::new();
     loop {
         match receiver.recv() {
@@ -47,14 +95,14 @@ pub fn server(receiver: Receiver<Command>) {
                 response_channel,
             }) => {
                 let id = store.add_ticket(draft);
-                todo!()
+                let _ = response_channel.send(id);
             }
             Ok(Command::Get {
                 id,
                 response_channel,
             }) => {
                 let ticket = store.get(id);
-                todo!()
+                let _ = response_channel.send(ticket.cloned());
             }
             Err(_) => {
                 // There are no more senders, so we can safely break
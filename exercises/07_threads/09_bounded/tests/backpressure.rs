@@ -0,0 +1,35 @@
+use bounded::data::TicketDraft;
+use bounded::{launch, launch_with, Backpressure};
+use std::time::Duration;
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+#[test]
+fn the_policy_is_visible_in_the_metrics() {
+    let policies = [
+        Backpressure::FailFast,
+        Backpressure::Block {
+            timeout: Duration::from_millis(10),
+        },
+        Backpressure::Retry {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(1),
+        },
+        Backpressure::Prioritized { read_capacity: 1 },
+    ];
+    for policy in policies {
+        let client = launch_with(1, policy);
+        let id = client
+            .insert(TicketDraft {
+                title: ticket_title(),
+                description: ticket_description(),
+            })
+            .unwrap();
+        assert!(client.get(id).unwrap().is_some());
+
+        let metrics = client.metrics();
+        assert_eq!(metrics.policy, policy);
+        assert_eq!(metrics.sent, 2);
+        assert_eq!(metrics.overloaded, 0);
+    }
+    assert_eq!(launch(1).metrics().policy, Backpressure::FailFast);
+}
@@ -0,0 +1,260 @@
+//! What clients do when a server's command queue is full.
+
+use crate::{ClientError, Command};
+use rand::Rng;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvError, SyncSender, TryRecvError, TrySendError};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How a client deals with a full command queue. It's chosen at launch time.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum Backpressure {
+    /// Fail with [`ClientError::Overloaded`] right away.
+    #[default]
+    FailFast,
+    /// Wait for room in the queue, for at most `timeout`.
+    Block { timeout: Duration },
+    /// Try again up to `max_attempts` times in total, waiting a random delay between
+    /// zero and `base_delay * 2^n` after the `n`-th failed attempt.
+    Retry {
+        max_attempts: u32,
+        base_delay: Duration,
+    },
+    /// Give reads a queue of their own, with room for `read_capacity` commands,
+    /// which the server serves before any queued write. Both queues fail fast.
+    Prioritized { read_capacity: usize },
+}
+
+/// Counters shared by a client and all its clones.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ClientMetrics {
+    pub policy: Backpressure,
+    /// The commands accepted by the server's queue.
+    pub sent: u64,
+    /// The calls that failed because the queue was full.
+    pub overloaded: u64,
+    /// The attempts made after the first one, under [`Backpressure::Retry`].
+    pub retries: u64,
+    /// The time spent waiting for room in the queue.
+    pub waited: Duration,
+}
+
+#[derive(Default)]
+pub(crate) struct Counters {
+    sent: AtomicU64,
+    overloaded: AtomicU64,
+    retries: AtomicU64,
+    waited_nanos: AtomicU64,
+}
+
+impl Counters {
+    pub(crate) fn snapshot(&self, policy: Backpressure) -> ClientMetrics {
+        ClientMetrics {
+            policy,
+            sent: self.sent.load(Ordering::Relaxed),
+            overloaded: self.overloaded.load(Ordering::Relaxed),
+            retries: self.retries.load(Ordering::Relaxed),
+            waited: Duration::from_nanos(self.waited_nanos.load(Ordering::Relaxed)),
+        }
+    }
+
+    fn wait(&self, duration: Duration) {
+        std::thread::sleep(duration);
+        self.waited_nanos
+            .fetch_add(duration.as_nanos() as u64, Ordering::Relaxed);
+    }
+}
+
+/// The sending side of a server's command queues.
+#[derive(Clone)]
+pub(crate) struct Outbox {
+    commands: SyncSender<Command>,
+    reads: Option<Priority>,
+    depth: Arc<AtomicUsize>,
+}
+
+#[derive(Clone)]
+struct Priority {
+    commands: SyncSender<Command>,
+    /// Rung once per queued command, read or write, so that the server can block
+    /// until there's something in either queue.
+    doorbell: SyncSender<()>,
+}
+
+/// The receiving side of a server's command queues.
+pub(crate) struct Inbox {
+    commands: Receiver<Command>,
+    reads: Option<(Receiver<Command>, Receiver<()>)>,
+    /// The commands in the queues. Incremented before queueing a command, so that
+    /// it never goes below zero, and decremented once it's received.
+    depth: Arc<AtomicUsize>,
+}
+
+/// Creates the command queues of a server, with room for `capacity` commands.
+pub(crate) fn queue(capacity: usize, policy: Backpressure) -> (Outbox, Inbox) {
+    let (sender, receiver) = sync_channel(capacity);
+    let depth = Arc::new(AtomicUsize::new(0));
+    let Backpressure::Prioritized { read_capacity } = policy else {
+        let outbox = Outbox {
+            commands: sender,
+            reads: None,
+            depth: Arc::clone(&depth),
+        };
+        let inbox = Inbox {
+            commands: receiver,
+            reads: None,
+            depth,
+        };
+        return (outbox, inbox);
+    };
+    let (read_sender, read_receiver) = sync_channel(read_capacity);
+    // There are never more rings pending than queued commands, so ringing never blocks.
+    let (doorbell, doorbell_receiver) = sync_channel(capacity + read_capacity);
+    let outbox = Outbox {
+        commands: sender,
+        reads: Some(Priority {
+            commands: read_sender,
+            doorbell,
+        }),
+        depth: Arc::clone(&depth),
+    };
+    let inbox = Inbox {
+        commands: receiver,
+        reads: Some((read_receiver, doorbell_receiver)),
+        depth,
+    };
+    (outbox, inbox)
+}
+
+impl Outbox {
+    /// Queues `command`, dealing with a full queue according to `policy`.
+    pub(crate) fn send(
+        &self,
+        mut command: Command,
+        policy: Backpressure,
+        counters: &Counters,
+    ) -> Result<(), ClientError> {
+        let deadline = match policy {
+            Backpressure::Block { timeout } => Some(Instant::now() + timeout),
+            _ => None,
+        };
+        let mut attempts = 1;
+        loop {
+            match self.try_send(command) {
+                Ok(()) => {
+                    counters.sent.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                Err(TrySendError::Disconnected(_)) => return Err(ClientError::ServerGone),
+                Err(TrySendError::Full(rejected)) => command = rejected,
+            }
+            let delay = match policy {
+                Backpressure::Block { .. } => deadline
+                    .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                    .filter(|remaining| !remaining.is_zero())
+                    .map(|remaining| remaining.min(POLL_INTERVAL)),
+                Backpressure::Retry {
+                    max_attempts,
+                    base_delay,
+                } if attempts < max_attempts => {
+                    counters.retries.fetch_add(1, Ordering::Relaxed);
+                    Some(jitter(
+                        base_delay.saturating_mul(1 << (attempts - 1).min(16)),
+                    ))
+                }
+                _ => None,
+            };
+            let Some(delay) = delay else {
+                counters.overloaded.fetch_add(1, Ordering::Relaxed);
+                return Err(ClientError::Overloaded);
+            };
+            counters.wait(delay);
+            attempts += 1;
+        }
+    }
+
+    /// Queues `command`, waiting at most `timeout` for room in the queue.
+    pub(crate) fn send_within(
+        &self,
+        command: Command,
+        timeout: Duration,
+    ) -> Result<(), ClientError> {
+        let policy = Backpressure::Block { timeout };
+        self.send(command, policy, &Counters::default())
+            .map_err(|e| match e {
+                ClientError::Overloaded => ClientError::Timeout,
+                e => e,
+            })
+    }
+
+    fn try_send(&self, command: Command) -> Result<(), TrySendError<Command>> {
+        self.depth.fetch_add(1, Ordering::SeqCst);
+        let sent = match &self.reads {
+            Some(reads) if command.is_read() => reads.commands.try_send(command),
+            _ => self.commands.try_send(command),
+        };
+        if sent.is_err() {
+            self.depth.fetch_sub(1, Ordering::SeqCst);
+        }
+        sent?;
+        self.ring();
+        Ok(())
+    }
+
+    fn ring(&self) {
+        if let Some(reads) = &self.reads {
+            // Only fails if the server is gone, and then nobody is listening anyway.
+            let _ = reads.doorbell.send(());
+        }
+    }
+}
+
+impl Inbox {
+    /// Waits for the next command. Queued reads come before queued writes.
+    pub(crate) fn recv(&self) -> Result<Command, RecvError> {
+        let command = match &self.reads {
+            None => self.commands.recv()?,
+            Some((reads, doorbell)) => {
+                doorbell.recv()?;
+                self.next(reads)
+            }
+        };
+        self.depth.fetch_sub(1, Ordering::SeqCst);
+        Ok(command)
+    }
+
+    pub(crate) fn try_recv(&self) -> Result<Command, TryRecvError> {
+        let command = match &self.reads {
+            None => self.commands.try_recv()?,
+            Some((reads, doorbell)) => {
+                doorbell.try_recv()?;
+                self.next(reads)
+            }
+        };
+        self.depth.fetch_sub(1, Ordering::SeqCst);
+        Ok(command)
+    }
+
+    /// The number of commands waiting in the queues.
+    pub(crate) fn depth(&self) -> usize {
+        self.depth.load(Ordering::SeqCst)
+    }
+
+    /// Takes a command after a ring of the doorbell.
+    fn next(&self, reads: &Receiver<Command>) -> Command {
+        // Commands are queued before the doorbell rings.
+        reads
+            .try_recv()
+            .or_else(|_| self.commands.try_recv())
+            .expect("every ring of the doorbell is preceded by a queued command")
+    }
+}
+
+/// How often a blocked client checks whether the queue has room again.
+const POLL_INTERVAL: Duration = Duration::from_millis(1);
+
+/// A random duration between zero and `max`.
+fn jitter(max: Duration) -> Duration {
+    rand::thread_rng().gen_range(Duration::ZERO..=max)
+}
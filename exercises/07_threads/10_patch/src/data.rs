@@ -7,6 +7,8 @@ pub struct Ticket {
     pub title: TicketTitle,
     pub description: TicketDescription,
     pub status: Status,
+    /// Starts at 1 and goes up by one every time a patch is applied.
+    pub version: u64,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -21,6 +23,8 @@ pub struct TicketPatch {
     pub title: Option<TicketTitle>,
     pub description: Option<TicketDescription>,
     pub status: Option<Status>,
+    /// If set, the patch is only applied if the ticket is still at this version.
+    pub expected_version: Option<u64>,
 }
 
 #[derive(Clone, Debug, Copy, PartialEq, Eq)]
@@ -29,3 +33,52 @@ pub enum Status {
     InProgress,
     Done,
 }
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("The ticket was modified concurrently: it is now at version {current_version}")]
+pub struct Conflict {
+    pub current_version: u64,
+}
+
+/// Why one patch of a batch was rejected.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum PatchError {
+    #[error("There is no ticket with id {0:?}")]
+    TicketNotFound(TicketId),
+    #[error(transparent)]
+    Conflict(#[from] Conflict),
+}
+
+/// Every patch of a batch that was rejected, by position in the batch.
+///
+/// When a batch is rejected, none of its patches is applied.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{} patch(es) of the batch were rejected, so none was applied", .failures.len())]
+pub struct BulkError {
+    pub failures: Vec<(usize, PatchError)>,
+}
+
+impl Ticket {
+    /// Applies `patch`, unless it expects a version other than the current one.
+    pub fn apply(&mut self, patch: TicketPatch) -> Result<(), Conflict> {
+        if patch
+            .expected_version
+            .is_some_and(|expected| expected != self.version)
+        {
+            return Err(Conflict {
+                current_version: self.version,
+            });
+        }
+        if let Some(title) = patch.title {
+            self.title = title;
+        }
+        if let Some(description) = patch.description {
+            self.description = description;
+        }
+        if let Some(status) = patch.status {
+            self.status = status;
+        }
+        self.version += 1;
+        Ok(())
+    }
+}
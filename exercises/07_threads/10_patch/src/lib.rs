@@ -1,51 +1,409 @@
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::mpsc::{sync_channel, Receiver, RecvTimeoutError, SyncSender};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
-// TODO: Implement the patching functionality.
-use crate::data::{Ticket, TicketDraft, TicketPatch};
+use crate::backpressure::{Counters, Inbox, Outbox};
+use crate::data::{BulkError, PatchError, Status, Ticket, TicketDraft, TicketPatch};
+use crate::metrics::{CommandKind, ServerMetrics};
 use crate::store::{TicketId, TicketStore};
 
+pub mod backpressure;
 pub mod data;
+pub mod metrics;
 pub mod store;
 
+pub use backpressure::{Backpressure, ClientMetrics};
+
 #[derive(Clone)]
 pub struct TicketStoreClient {
-    sender: SyncSender<Command>,
+    /// One sender per shard. Shard `i` owns the tickets whose id is `i` modulo the
+    /// number of shards.
+    shards: Arc<[Outbox]>,
+    /// The shard that the next insertion goes to, round-robin.
+    next_shard: Arc<AtomicUsize>,
+    timeout: Duration,
+    policy: Backpressure,
+    counters: Arc<Counters>,
+    gate: Arc<Gate>,
+}
+
+/// Shared by the clients and the server, to refuse new commands once shutting down.
+#[derive(Default)]
+struct Gate {
+    closed: AtomicBool,
+    /// The calls refused because the server was shutting down.
+    rejected: AtomicUsize,
 }
 
 impl TicketStoreClient {
-    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, OverloadedError> {
-        let (response_sender, response_receiver) = sync_channel(1);
-        self.sender
-            .try_send(Command::Insert {
-                draft,
-                response_channel: response_sender,
+    /// How long a call waits for the server's response, unless configured otherwise.
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// A client whose calls wait at most `timeout` for the server's response.
+    ///
+    /// Clients are cheap to clone, so this also works for a single call:
+    /// `client.with_timeout(Duration::from_millis(100)).get(id)`.
+    pub fn with_timeout(&self, timeout: Duration) -> Self {
+        Self {
+            timeout,
+            ..self.clone()
+        }
+    }
+
+    pub fn metrics(&self) -> ClientMetrics {
+        self.counters.snapshot(self.policy)
+    }
+
+    pub fn insert(&self, draft: TicketDraft) -> Result<TicketId, ClientError> {
+        self.request(self.insertion_shard(), |response_channel| Command::Insert {
+            draft,
+            response_channel,
+        })
+    }
+
+    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, ClientError> {
+        self.request(self.shard_of(id), |response_channel| Command::Get {
+            id,
+            response_channel,
+        })
+    }
+
+    /// Applies `ticket_patch`. Patching a ticket that doesn't exist is rejected with
+    /// [`PatchError::TicketNotFound`].
+    pub fn update(&self, ticket_patch: TicketPatch) -> Result<(), UpdateError> {
+        Ok(
+            self.request(self.shard_of(ticket_patch.id), |response_channel| {
+                Command::Update {
+                    patch: ticket_patch,
+                    response_channel,
+                }
+            })??,
+        )
+    }
+
+    /// Adds every draft in a single round-trip, returning the new ids in the same order.
+    pub fn insert_many(&self, drafts: Vec<TicketDraft>) -> Result<Vec<TicketId>, ClientError> {
+        self.request(self.insertion_shard(), |response_channel| {
+            Command::InsertMany {
+                drafts,
+                response_channel,
+            }
+        })
+    }
+
+    /// Applies every patch, or none of them if any is rejected.
+    ///
+    /// If the patches span several shards, each of them is locked in turn, in
+    /// ascending order so that concurrent batches can't deadlock, until all of them
+    /// have checked their part of the batch.
+    pub fn update_many(&self, patches: Vec<TicketPatch>) -> Result<(), UpdateManyError> {
+        let mut by_shard: BTreeMap<usize, (Vec<usize>, Vec<TicketPatch>)> = BTreeMap::new();
+        for (position, patch) in patches.into_iter().enumerate() {
+            let (positions, patches) = by_shard.entry(self.shard_of(patch.id)).or_default();
+            positions.push(position);
+            patches.push(patch);
+        }
+        if by_shard.len() == 1 {
+            // Positions within the only part are positions within the batch.
+            let (shard, (_, patches)) = by_shard.pop_first().unwrap();
+            return Ok(self.request(shard, |response_channel| Command::UpdateMany {
+                patches,
+                response_channel,
+            })??);
+        }
+        let deadline = Instant::now() + self.timeout;
+        let mut failures = Vec::new();
+        // Dropping a decision before sending it aborts the shard's part of the batch.
+        let mut decisions = Vec::new();
+        for (shard, (positions, patches)) in by_shard {
+            let (decision_sender, decision) = sync_channel(1);
+            let response = self.send(shard, |response_channel| Command::Prepare {
+                patches,
+                response_channel,
+                decision,
+                deadline,
+            })?;
+            if let Err(rejected) = receive(&response, deadline)? {
+                failures.extend(
+                    rejected
+                        .failures
+                        .into_iter()
+                        .map(|(i, error)| (positions[i], error)),
+                );
+            }
+            decisions.push(decision_sender);
+        }
+        let commit = failures.is_empty();
+        // Past the deadline, the shards may have given up on the batch already.
+        // Dropping the decisions aborts it on the others.
+        if commit && Instant::now() >= deadline {
+            return Err(ClientError::Timeout.into());
+        }
+        for decision in decisions {
+            let _ = decision.send(commit);
+        }
+        if !commit {
+            failures.sort_by_key(|(position, _)| *position);
+            return Err(BulkError { failures }.into());
+        }
+        Ok(())
+    }
+
+    /// Every ticket, ordered by id.
+    pub fn list(&self) -> Result<Vec<Ticket>, ClientError> {
+        self.scan(None)
+    }
+
+    /// The tickets with the given status, ordered by id.
+    pub fn list_by_status(&self, status: Status) -> Result<Vec<Ticket>, ClientError> {
+        self.scan(Some(status))
+    }
+
+    /// The server's metrics, added up across shards.
+    pub fn stats(&self) -> Result<ServerMetrics, ClientError> {
+        let responses = (0..self.shards.len())
+            .map(|shard| {
+                self.send(shard, |response_channel| Command::Stats {
+                    response_channel,
+                })
             })
-            .map_err(|_| OverloadedError)?;
-        Ok(response_receiver.recv().unwrap())
+            .collect::<Result<Vec<_>, _>>()?;
+        let deadline = Instant::now() + self.timeout;
+        let mut metrics = ServerMetrics::default();
+        for response in responses {
+            metrics.merge(&receive(&response, deadline)?);
+        }
+        Ok(metrics)
     }
 
-    pub fn get(&self, id: TicketId) -> Result<Option<Ticket>, OverloadedError> {
-        let (response_sender, response_receiver) = sync_channel(1);
-        self.sender
-            .try_send(Command::Get {
-                id,
-                response_channel: response_sender,
+    /// Asks every shard at once, then merges their answers.
+    fn scan(&self, status: Option<Status>) -> Result<Vec<Ticket>, ClientError> {
+        let responses = (0..self.shards.len())
+            .map(|shard| {
+                self.send(shard, |response_channel| Command::List {
+                    status,
+                    response_channel,
+                })
             })
-            .map_err(|_| OverloadedError)?;
-        Ok(response_receiver.recv().unwrap())
+            .collect::<Result<Vec<_>, _>>()?;
+        let deadline = Instant::now() + self.timeout;
+        let mut tickets = Vec::new();
+        for response in responses {
+            tickets.extend(receive(&response, deadline)?);
+        }
+        tickets.sort_by_key(|ticket| ticket.id);
+        Ok(tickets)
+    }
+
+    fn shard_of(&self, id: TicketId) -> usize {
+        id.shard(self.shards.len())
+    }
+
+    fn insertion_shard(&self) -> usize {
+        self.next_shard.fetch_add(1, Ordering::Relaxed) % self.shards.len()
     }
 
-    pub fn update(&self, ticket_patch: TicketPatch) -> Result<(), OverloadedError> {}
+    /// Sends a command to a shard and waits for its response.
+    ///
+    /// A command that timed out may still be executed by the server later on.
+    fn request<T>(
+        &self,
+        shard: usize,
+        command: impl FnOnce(SyncSender<T>) -> Command,
+    ) -> Result<T, ClientError> {
+        let response = self.send(shard, command)?;
+        receive(&response, Instant::now() + self.timeout)
+    }
+
+    fn send<T>(
+        &self,
+        shard: usize,
+        command: impl FnOnce(SyncSender<T>) -> Command,
+    ) -> Result<Receiver<T>, ClientError> {
+        if self.gate.closed.load(Ordering::SeqCst) {
+            self.gate.rejected.fetch_add(1, Ordering::SeqCst);
+            return Err(ClientError::ServerGone);
+        }
+        let (response_sender, response_receiver) = sync_channel(1);
+        self.shards[shard].send(command(response_sender), self.policy, &self.counters)?;
+        Ok(response_receiver)
+    }
+}
+
+/// How long past a batch's deadline its shards still wait for the decision.
+const DECISION_GRACE: Duration = Duration::from_millis(100);
+
+fn receive<T>(response: &Receiver<T>, deadline: Instant) -> Result<T, ClientError> {
+    response
+        .recv_timeout(deadline.saturating_duration_since(Instant::now()))
+        .map_err(|e| match e {
+            RecvTimeoutError::Timeout => ClientError::Timeout,
+            RecvTimeoutError::Disconnected => ClientError::ServerGone,
+        })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum ClientError {
+    #[error("The store is overloaded")]
+    Overloaded,
+    #[error("The store server has shut down")]
+    ServerGone,
+    #[error("The store server did not respond in time")]
+    Timeout,
 }
 
 #[derive(Debug, thiserror::Error)]
-#[error("The store is overloaded")]
-pub struct OverloadedError;
+pub enum UpdateError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error(transparent)]
+    Rejected(#[from] PatchError),
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum UpdateManyError {
+    #[error(transparent)]
+    Client(#[from] ClientError),
+    #[error(transparent)]
+    Rejected(#[from] BulkError),
+}
 
 pub fn launch(capacity: usize) -> TicketStoreClient {
-    let (sender, receiver) = sync_channel(capacity);
-    std::thread::spawn(move || server(receiver));
-    TicketStoreClient { sender }
+    launch_with(capacity, Backpressure::default())
+}
+
+/// Like [`launch`], with the given policy for when the command queue is full.
+pub fn launch_with(capacity: usize, policy: Backpressure) -> TicketStoreClient {
+    start(1, capacity, policy).0
+}
+
+/// Like [`launch`], but also returns a handle to shut the server down explicitly.
+pub fn launch_with_handle(capacity: usize) -> (TicketStoreClient, ServerHandle) {
+    with_handle(start(1, capacity, Backpressure::default()))
+}
+
+/// Partitions the tickets across `n_shards` server threads, each with its own
+/// command queue of the given `capacity`.
+///
+/// # Panics
+///
+/// Panics if `n_shards` is zero.
+pub fn launch_sharded(n_shards: usize, capacity: usize) -> TicketStoreClient {
+    launch_sharded_with_handle(n_shards, capacity).0
+}
+
+/// Like [`launch_sharded`], but also returns a handle to shut every shard down at once.
+///
+/// # Panics
+///
+/// Panics if `n_shards` is zero.
+pub fn launch_sharded_with_handle(
+    n_shards: usize,
+    capacity: usize,
+) -> (TicketStoreClient, ServerHandle) {
+    assert!(n_shards > 0, "a sharded server needs at least one shard");
+    with_handle(start(n_shards, capacity, Backpressure::default()))
+}
+
+fn with_handle(
+    (client, threads): (TicketStoreClient, Vec<JoinHandle<(TicketStore, usize)>>),
+) -> (TicketStoreClient, ServerHandle) {
+    let handle = ServerHandle {
+        shards: Arc::clone(&client.shards),
+        gate: Arc::clone(&client.gate),
+        threads,
+        timeout: TicketStoreClient::DEFAULT_TIMEOUT,
+    };
+    (client, handle)
+}
+
+fn start(
+    n_shards: usize,
+    capacity: usize,
+    policy: Backpressure,
+) -> (TicketStoreClient, Vec<JoinHandle<(TicketStore, usize)>>) {
+    let gate = Arc::new(Gate::default());
+    let (shards, threads): (Vec<_>, _) = (0..n_shards)
+        .map(|shard| {
+            let (outbox, inbox) = backpressure::queue(capacity, policy);
+            let gate = Arc::clone(&gate);
+            let store = TicketStore::for_shard(shard, n_shards);
+            let thread = std::thread::spawn(move || server(inbox, store, &gate));
+            (outbox, thread)
+        })
+        .unzip();
+    let client = TicketStoreClient {
+        shards: shards.into(),
+        next_shard: Arc::default(),
+        timeout: TicketStoreClient::DEFAULT_TIMEOUT,
+        policy,
+        counters: Arc::default(),
+        gate,
+    };
+    (client, threads)
+}
+
+pub struct ServerHandle {
+    shards: Arc<[Outbox]>,
+    gate: Arc<Gate>,
+    threads: Vec<JoinHandle<(TicketStore, usize)>>,
+    timeout: Duration,
+}
+
+/// What the server did while shutting down, and the state it left behind.
+pub struct ShutdownReport {
+    /// The tickets of every shard.
+    pub store: TicketStore,
+    /// The store commands that were already queued, and executed before stopping.
+    pub drained: usize,
+    /// The calls refused because the server was shutting down.
+    pub rejected: usize,
+}
+
+impl ServerHandle {
+    /// A handle whose shutdown waits at most `timeout` for room in the server's queue.
+    pub fn with_timeout(self, timeout: Duration) -> Self {
+        Self { timeout, ..self }
+    }
+
+    /// Stops accepting new commands, executes the ones already queued,
+    /// and waits for every shard to stop.
+    ///
+    /// Calls made from now on fail with [`ClientError::ServerGone`].
+    /// Fails with [`ClientError::Timeout`] if a queue stays full for longer than
+    /// the handle's timeout: the shards that didn't get the request to shut down
+    /// are then left running, but still refuse new commands.
+    pub fn shutdown(self) -> Result<ShutdownReport, ClientError> {
+        self.gate.closed.store(true, Ordering::SeqCst);
+        let deadline = Instant::now() + self.timeout;
+        for outbox in self.shards.iter() {
+            // The shard is draining its queue, so there should be room soon.
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            match outbox.send_within(Command::Shutdown, timeout) {
+                // If the shard already stopped, there's nothing left to drain anyway.
+                Ok(()) | Err(ClientError::ServerGone) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let mut stores = Vec::with_capacity(self.threads.len());
+        let mut drained = 0;
+        for thread in self.threads {
+            let (store, shard_drained) = match thread.join() {
+                Ok(output) => output,
+                Err(panic) => std::panic::resume_unwind(panic),
+            };
+            stores.push(store);
+            drained += shard_drained;
+        }
+        Ok(ShutdownReport {
+            store: TicketStore::merge(stores),
+            drained,
+            rejected: self.gate.rejected.load(Ordering::SeqCst),
+        })
+    }
 }
 
 enum Command {
@@ -59,34 +417,80 @@ enum Command {
     },
     Update {
         patch: TicketPatch,
-        response_channel: SyncSender<()>,
+        response_channel: SyncSender<Result<(), PatchError>>,
+    },
+    InsertMany {
+        drafts: Vec<TicketDraft>,
+        response_channel: SyncSender<Vec<TicketId>>,
+    },
+    UpdateMany {
+        patches: Vec<TicketPatch>,
+        response_channel: SyncSender<Result<(), BulkError>>,
+    },
+    /// The first phase of a batch spanning several shards: the shard checks its
+    /// part of the batch, and if it can be applied, waits for the final decision.
+    /// Without a decision by `deadline`, the shard aborts its part.
+    Prepare {
+        patches: Vec<TicketPatch>,
+        response_channel: SyncSender<Result<(), BulkError>>,
+        decision: Receiver<bool>,
+        deadline: Instant,
+    },
+    List {
+        status: Option<Status>,
+        response_channel: SyncSender<Vec<Ticket>>,
     },
+    Stats {
+        response_channel: SyncSender<ServerMetrics>,
+    },
+    /// Sent by [`ServerHandle::shutdown`], after every command that was already queued.
+    Shutdown,
+}
+
+impl Command {
+    /// Whether the command leaves the store unchanged.
+    fn is_read(&self) -> bool {
+        matches!(
+            self,
+            Command::Get { .. } | Command::List { .. } | Command::Stats { .. }
+        )
+    }
+
+    /// The kind of the command, if it's a store command, whose execution is measured.
+    fn kind(&self) -> Option<CommandKind> {
+        match self {
+            Command::Insert { .. } => Some(CommandKind::Insert),
+            Command::Get { .. } => Some(CommandKind::Get),
+            Command::Update { .. } => Some(CommandKind::Update),
+            Command::InsertMany { .. } => Some(CommandKind::InsertMany),
+            Command::UpdateMany { .. } => Some(CommandKind::UpdateMany),
+            Command::Prepare { .. } => Some(CommandKind::Prepare),
+            Command::List { .. } => Some(CommandKind::List),
+            Command::Stats { .. } | Command::Shutdown => None,
+        }
+    }
 }
 
-pub fn server(receiver: Receiver<Command>) {
-    let mut store = TicketStore::new();
+/// Returns the final state of the store, and how many commands were drained while shutting down.
+fn server(receiver: Inbox, mut store: TicketStore, gate: &Gate) -> (TicketStore, usize) {
+    let mut metrics = ServerMetrics::default();
+    let mut drained = 0;
     loop {
         match receiver.recv() {
-            Ok(Command::Insert {
-                draft,
-                response_channel,
-            }) => {
-                let id = store.add_ticket(draft);
-                let _ = response_channel.send(id);
+            Ok(Command::Shutdown) => {
+                // Clients that passed the gate right before it closed may have
+                // queued commands after ours: execute those too.
+                while let Ok(command) = receiver.try_recv() {
+                    drained += usize::from(command.kind().is_some());
+                    handle(&mut store, &mut metrics, &receiver, command);
+                }
+                break;
             }
-            Ok(Command::Get {
-                id,
-                response_channel,
-            }) => {
-                let ticket = store.get(id);
-                let _ = response_channel.send(ticket.cloned());
-            }
-            Ok(Command::Update {
-                patch,
-                response_channel,
-            }) => {
-                todo!()
+            Ok(command) if gate.closed.load(Ordering::SeqCst) => {
+                drained += usize::from(command.kind().is_some());
+                handle(&mut store, &mut metrics, &receiver, command);
             }
+            Ok(command) => handle(&mut store, &mut metrics, &receiver, command),
             Err(_) => {
                 // There are no more senders, so we can safely break
                 // and shut down the server.
@@ -94,4 +498,89 @@ pub fn server(receiver: Receiver<Command>) {
             }
         }
     }
+    (store, drained)
+}
+
+/// Executes `command`, measuring how long it takes.
+fn handle(store: &mut TicketStore, metrics: &mut ServerMetrics, inbox: &Inbox, command: Command) {
+    let kind = command.kind();
+    let started = Instant::now();
+    execute(store, metrics, inbox, command);
+    if let Some(kind) = kind {
+        metrics.record(kind, started.elapsed());
+    }
+}
+
+fn execute(store: &mut TicketStore, metrics: &ServerMetrics, inbox: &Inbox, command: Command) {
+    match command {
+        Command::Insert {
+            draft,
+            response_channel,
+        } => {
+            let id = store.add_ticket(draft);
+            let _ = response_channel.send(id);
+        }
+        Command::Get {
+            id,
+            response_channel,
+        } => {
+            let ticket = store.get(id);
+            let _ = response_channel.send(ticket.cloned());
+        }
+        Command::Update {
+            patch,
+            response_channel,
+        } => {
+            let _ = response_channel.send(store.patch(patch));
+        }
+        Command::InsertMany {
+            drafts,
+            response_channel,
+        } => {
+            let ids = store.insert_many(drafts);
+            let _ = response_channel.send(ids);
+        }
+        Command::UpdateMany {
+            patches,
+            response_channel,
+        } => {
+            let _ = response_channel.send(store.patch_many(patches));
+        }
+        Command::Prepare {
+            patches,
+            response_channel,
+            decision,
+            deadline,
+        } => match store.stage_many(patches) {
+            Ok(staged) => {
+                let _ = response_channel.send(Ok(()));
+                // Nothing else runs on this shard until the batch is decided,
+                // so the staged tickets can't go stale.
+                // The grace period covers a commit sent right before the deadline.
+                let timeout = (deadline + DECISION_GRACE).saturating_duration_since(Instant::now());
+                if decision.recv_timeout(timeout) == Ok(true) {
+                    store.commit_staged(staged);
+                }
+            }
+            Err(rejected) => {
+                let _ = response_channel.send(Err(rejected));
+            }
+        },
+        Command::List {
+            status,
+            response_channel,
+        } => {
+            let _ = response_channel.send(store.list(status));
+        }
+        Command::Stats { response_channel } => {
+            let snapshot = ServerMetrics {
+                queue_depth: inbox.depth(),
+                store_size: store.len(),
+                ..metrics.clone()
+            };
+            let _ = response_channel.send(snapshot);
+        }
+        // The server loop stops on it instead of executing it.
+        Command::Shutdown => {}
+    }
 }
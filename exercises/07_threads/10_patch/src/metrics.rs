@@ -0,0 +1,160 @@
+//! Metrics collected by the ticket server as it executes commands.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::time::Duration;
+
+/// The kinds of commands that the server measures.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum CommandKind {
+    Insert,
+    Get,
+    Update,
+    InsertMany,
+    UpdateMany,
+    /// The first phase of a batch spanning several shards, including the wait
+    /// for the final decision.
+    Prepare,
+    List,
+}
+
+impl CommandKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            CommandKind::Insert => "insert",
+            CommandKind::Get => "get",
+            CommandKind::Update => "update",
+            CommandKind::InsertMany => "insert_many",
+            CommandKind::UpdateMany => "update_many",
+            CommandKind::Prepare => "prepare",
+            CommandKind::List => "list",
+        }
+    }
+}
+
+/// The upper bounds of the latency histogram buckets, in seconds.
+pub const LATENCY_BUCKETS: [f64; 11] = [
+    0.00001, 0.00005, 0.0001, 0.0005, 0.001, 0.005, 0.01, 0.05, 0.1, 0.5, 1.0,
+];
+
+/// How long the commands of one kind took to execute.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct LatencyHistogram {
+    /// The number of commands that fell in each of [`LATENCY_BUCKETS`], and then
+    /// the number that took longer than the last bound.
+    pub buckets: [u64; LATENCY_BUCKETS.len() + 1],
+    pub sum: Duration,
+}
+
+impl LatencyHistogram {
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().sum()
+    }
+
+    fn record(&mut self, latency: Duration) {
+        let seconds = latency.as_secs_f64();
+        let bucket = LATENCY_BUCKETS.partition_point(|&bound| bound < seconds);
+        self.buckets[bucket] += 1;
+        self.sum += latency;
+    }
+
+    fn merge(&mut self, other: &LatencyHistogram) {
+        for (bucket, count) in self.buckets.iter_mut().zip(other.buckets) {
+            *bucket += count;
+        }
+        self.sum += other.sum;
+    }
+}
+
+/// A snapshot of the server's metrics.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ServerMetrics {
+    /// The latencies of the commands executed so far, by kind.
+    /// Kinds that were never executed are missing.
+    pub commands: BTreeMap<CommandKind, LatencyHistogram>,
+    /// The commands waiting to be executed when the snapshot was taken.
+    pub queue_depth: usize,
+    /// The tickets in the store when the snapshot was taken.
+    pub store_size: usize,
+}
+
+impl ServerMetrics {
+    pub(crate) fn record(&mut self, kind: CommandKind, latency: Duration) {
+        self.commands.entry(kind).or_default().record(latency);
+    }
+
+    /// Adds up the metrics of another shard.
+    pub fn merge(&mut self, other: &ServerMetrics) {
+        for (kind, histogram) in &other.commands {
+            self.commands.entry(*kind).or_default().merge(histogram);
+        }
+        self.queue_depth += other.queue_depth;
+        self.store_size += other.store_size;
+    }
+
+    /// Renders the metrics in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        // Writing to a `String` can't fail.
+        let _ = self.write_prometheus(&mut out);
+        out
+    }
+
+    fn write_prometheus(&self, out: &mut String) -> std::fmt::Result {
+        writeln!(
+            out,
+            "# HELP ticket_commands_total Commands executed by the ticket server."
+        )?;
+        writeln!(out, "# TYPE ticket_commands_total counter")?;
+        for (kind, histogram) in &self.commands {
+            let name = kind.name();
+            writeln!(
+                out,
+                "ticket_commands_total{{command=\"{name}\"}} {}",
+                histogram.count()
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP ticket_command_duration_seconds Time spent executing commands."
+        )?;
+        writeln!(out, "# TYPE ticket_command_duration_seconds histogram")?;
+        for (kind, histogram) in &self.commands {
+            let name = kind.name();
+            let mut cumulative = 0;
+            for (bound, count) in LATENCY_BUCKETS.iter().zip(histogram.buckets) {
+                cumulative += count;
+                writeln!(
+                    out,
+                    "ticket_command_duration_seconds_bucket{{command=\"{name}\",le=\"{bound}\"}} {cumulative}"
+                )?;
+            }
+            writeln!(
+                out,
+                "ticket_command_duration_seconds_bucket{{command=\"{name}\",le=\"+Inf\"}} {}",
+                histogram.count()
+            )?;
+            writeln!(
+                out,
+                "ticket_command_duration_seconds_sum{{command=\"{name}\"}} {}",
+                histogram.sum.as_secs_f64()
+            )?;
+            writeln!(
+                out,
+                "ticket_command_duration_seconds_count{{command=\"{name}\"}} {}",
+                histogram.count()
+            )?;
+        }
+
+        writeln!(
+            out,
+            "# HELP ticket_queue_depth Commands waiting to be executed."
+        )?;
+        writeln!(out, "# TYPE ticket_queue_depth gauge")?;
+        writeln!(out, "ticket_queue_depth {}", self.queue_depth)?;
+        writeln!(out, "# HELP ticket_store_size Tickets in the store.")?;
+        writeln!(out, "# TYPE ticket_store_size gauge")?;
+        writeln!(out, "ticket_store_size {}", self.store_size)
+    }
+}
@@ -1,13 +1,22 @@
-use crate::data::{Status, Ticket, TicketDraft};
+use crate::data::{BulkError, PatchError, Status, Ticket, TicketDraft, TicketPatch};
 use std::collections::BTreeMap;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TicketId(u64);
 
+impl TicketId {
+    /// The shard that owns this ticket, out of `shards`.
+    pub(crate) fn shard(self, shards: usize) -> usize {
+        (self.0 % shards as u64) as usize
+    }
+}
+
 #[derive(Clone)]
 pub struct TicketStore {
     tickets: BTreeMap<TicketId, Ticket>,
     counter: u64,
+    /// How much `counter` goes up with each new ticket.
+    step: u64,
 }
 
 impl TicketStore {
@@ -15,17 +24,39 @@ impl TicketStore {
         Self {
             tickets: BTreeMap::new(),
             counter: 0,
+            step: 1,
         }
     }
 
+    /// The store of one of `shards` shards, which only assigns the ids owned by `shard`.
+    pub fn for_shard(shard: usize, shards: usize) -> Self {
+        Self {
+            tickets: BTreeMap::new(),
+            counter: shard as u64,
+            step: shards as u64,
+        }
+    }
+
+    /// Gathers the tickets of every shard into a single store.
+    pub fn merge(shards: impl IntoIterator<Item = TicketStore>) -> Self {
+        let mut merged = Self::new();
+        for shard in shards {
+            // A shard's counter is past every id it assigned.
+            merged.counter = merged.counter.max(shard.counter);
+            merged.tickets.extend(shard.tickets);
+        }
+        merged
+    }
+
     pub fn add_ticket(&mut self, ticket: TicketDraft) -> TicketId {
         let id = TicketId(self.counter);
-        self.counter += 1;
+        self.counter += self.step;
         let ticket = Ticket {
             id,
             title: ticket.title,
             description: ticket.description,
             status: Status::ToDo,
+            version: 1,
         };
         self.tickets.insert(id, ticket);
         id
@@ -35,7 +66,92 @@ impl TicketStore {
         self.tickets.get(&id)
     }
 
-    pub fn get_mut(&mut self, id: TicketId) -> Option<&mut Ticket> {
+    /// Crate-private: edits made through it would skip the version bump that
+    /// [`TicketStore::patch`] performs.
+    pub(crate) fn get_mut(&mut self, id: TicketId) -> Option<&mut Ticket> {
         self.tickets.get_mut(&id)
     }
+
+    /// Applies `patch`, unless the ticket doesn't exist or has moved past the
+    /// expected version.
+    pub fn patch(&mut self, patch: TicketPatch) -> Result<(), PatchError> {
+        match self.get_mut(patch.id) {
+            Some(ticket) => Ok(ticket.apply(patch)?),
+            None => Err(PatchError::TicketNotFound(patch.id)),
+        }
+    }
+
+    /// Every ticket, or only those with the given status, ordered by id.
+    pub fn list(&self, status: Option<Status>) -> Vec<Ticket> {
+        self.tickets
+            .values()
+            .filter(|ticket| status.is_none_or(|status| ticket.status == status))
+            .cloned()
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.tickets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tickets.is_empty()
+    }
+
+    /// Adds every draft, returning the new ids in the same order.
+    pub fn insert_many(&mut self, drafts: Vec<TicketDraft>) -> Vec<TicketId> {
+        drafts
+            .into_iter()
+            .map(|draft| self.add_ticket(draft))
+            .collect()
+    }
+
+    /// Applies every patch in order, or none of them if any is rejected.
+    pub fn patch_many(&mut self, patches: Vec<TicketPatch>) -> Result<(), BulkError> {
+        let staged = self.stage_many(patches)?;
+        self.commit_staged(staged);
+        Ok(())
+    }
+
+    /// Checks that every patch can be applied, returning the patched tickets
+    /// without storing them yet.
+    pub(crate) fn stage_many(
+        &self,
+        patches: Vec<TicketPatch>,
+    ) -> Result<BTreeMap<TicketId, Ticket>, BulkError> {
+        // Patches are applied to copies first, so that a batch patching
+        // the same ticket twice sees the version bumped by the first patch.
+        let mut staged: BTreeMap<TicketId, Ticket> = BTreeMap::new();
+        let mut failures = Vec::new();
+        for (position, patch) in patches.into_iter().enumerate() {
+            let id = patch.id;
+            let ticket = match staged.get_mut(&id) {
+                Some(ticket) => ticket,
+                None => match self.tickets.get(&id) {
+                    Some(ticket) => staged.entry(id).or_insert_with(|| ticket.clone()),
+                    None => {
+                        failures.push((position, PatchError::TicketNotFound(id)));
+                        continue;
+                    }
+                },
+            };
+            if let Err(conflict) = ticket.apply(patch) {
+                failures.push((position, conflict.into()));
+            }
+        }
+        if !failures.is_empty() {
+            return Err(BulkError { failures });
+        }
+        Ok(staged)
+    }
+
+    pub(crate) fn commit_staged(&mut self, staged: BTreeMap<TicketId, Ticket>) {
+        self.tickets.extend(staged);
+    }
+}
+
+impl Default for TicketStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
@@ -0,0 +1,117 @@
+use patch::data::TicketDraft;
+use patch::{launch_with, Backpressure, ClientError, TicketStoreClient};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+fn drafts(n: usize) -> Vec<TicketDraft> {
+    (0..n)
+        .map(|_| TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+        .collect()
+}
+
+/// Each test races its client against a busy server, so they run one at a time
+/// to keep the others from stealing the server's CPU time.
+fn serial() -> MutexGuard<'static, ()> {
+    static LOCK: Mutex<()> = Mutex::new(());
+    LOCK.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Keeps the server busy with a large batch, then queues small batches
+/// until the queue is full. Returns the error that reported it.
+fn fill(client: &TicketStoreClient) -> ClientError {
+    let impatient = client.with_timeout(Duration::ZERO);
+    assert_eq!(
+        impatient.insert_many(drafts(50_000)),
+        Err(ClientError::Timeout)
+    );
+    // Let the server take the batch off the queue, which would still be full otherwise.
+    std::thread::sleep(Duration::from_millis(2));
+    for _ in 0..100 {
+        match impatient.insert_many(drafts(1)) {
+            Err(ClientError::Timeout) => continue,
+            Err(e) => return e,
+            Ok(_) => panic!("the server should still be busy"),
+        }
+    }
+    panic!("the queue never filled up");
+}
+
+#[test]
+fn fail_fast() {
+    let _serial = serial();
+    let client = launch_with(1, Backpressure::FailFast);
+    assert_eq!(fill(&client), ClientError::Overloaded);
+
+    let metrics = client.metrics();
+    assert_eq!(metrics.policy, Backpressure::FailFast);
+    assert_eq!(metrics.overloaded, 1);
+    assert!(metrics.sent >= 2);
+    assert_eq!(metrics.retries, 0);
+    assert_eq!(metrics.waited, Duration::ZERO);
+}
+
+#[test]
+fn retry_with_backoff() {
+    let _serial = serial();
+    let policy = Backpressure::Retry {
+        max_attempts: 3,
+        base_delay: Duration::from_micros(100),
+    };
+    let client = launch_with(1, policy);
+    assert_eq!(fill(&client), ClientError::Overloaded);
+
+    let metrics = client.metrics();
+    assert_eq!(metrics.policy, policy);
+    assert_eq!(metrics.overloaded, 1);
+    assert_eq!(metrics.retries, 2);
+}
+
+#[test]
+fn block_until_deadline() {
+    let _serial = serial();
+    let client = launch_with(
+        1,
+        Backpressure::Block {
+            timeout: Duration::from_millis(2),
+        },
+    );
+    assert_eq!(fill(&client), ClientError::Overloaded);
+    assert!(client.metrics().waited > Duration::ZERO);
+
+    // With enough patience, the call goes through once the server catches up.
+    let patient = launch_with(
+        1,
+        Backpressure::Block {
+            timeout: Duration::from_secs(30),
+        },
+    );
+    let impatient = patient.with_timeout(Duration::ZERO);
+    impatient.insert_many(drafts(50_000)).unwrap_err();
+    impatient.insert_many(drafts(1)).unwrap_err();
+    assert_eq!(
+        patient
+            .with_timeout(Duration::from_secs(30))
+            .list()
+            .unwrap()
+            .len(),
+        50_001
+    );
+    let metrics = patient.metrics();
+    assert_eq!(metrics.overloaded, 0);
+    assert_eq!(metrics.sent, 3);
+}
+
+#[test]
+fn reads_bypass_queued_writes() {
+    let _serial = serial();
+    let client = launch_with(1, Backpressure::Prioritized { read_capacity: 1 });
+    assert_eq!(fill(&client), ClientError::Overloaded);
+
+    // The queued write hasn't been executed yet when the read is served.
+    let tickets = client.with_timeout(Duration::from_secs(30)).list().unwrap();
+    assert_eq!(tickets.len(), 50_000);
+}
@@ -1,5 +1,6 @@
-use patch::data::{Status, TicketDraft, TicketPatch};
-use patch::launch;
+use patch::data::{Conflict, PatchError, Status, TicketDraft, TicketPatch};
+use patch::{launch, launch_with_handle, ClientError, UpdateError, UpdateManyError};
+use std::time::Duration;
 use ticket_fields::test_helpers::{ticket_description, ticket_title};
 
 #[test]
@@ -22,6 +23,7 @@ fn works() {
         title: None,
         description: None,
         status: Some(Status::InProgress),
+        expected_version: None,
     };
     client.update(patch).unwrap();
 
@@ -29,3 +31,157 @@ fn works() {
     assert_eq!(ticket.id, ticket_id);
     assert_eq!(ticket.status, Status::InProgress);
 }
+
+#[test]
+fn rejects_stale_patches() {
+    let client = launch(5);
+    let draft = TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    };
+    let ticket_id = client.insert(draft).unwrap();
+    let ticket = client.get(ticket_id).unwrap().unwrap();
+    assert_eq!(ticket.version, 1);
+
+    let patch = |status| TicketPatch {
+        id: ticket_id,
+        title: None,
+        description: None,
+        status: Some(status),
+        expected_version: Some(ticket.version),
+    };
+    client.update(patch(Status::InProgress)).unwrap();
+    let err = client.update(patch(Status::Done)).unwrap_err();
+    assert!(matches!(
+        err,
+        UpdateError::Rejected(PatchError::Conflict(Conflict { current_version: 2 }))
+    ));
+
+    let ticket = client.get(ticket_id).unwrap().unwrap();
+    assert_eq!(ticket.status, Status::InProgress);
+    assert_eq!(ticket.version, 2);
+}
+
+#[test]
+fn rejects_patches_to_missing_tickets() {
+    let client = launch(5);
+    let other = launch(5);
+    let draft = || TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    };
+    other.insert(draft()).unwrap();
+    // An id that `client` never assigned.
+    let missing = other.insert(draft()).unwrap();
+    client.insert(draft()).unwrap();
+
+    let err = client
+        .update(TicketPatch {
+            id: missing,
+            title: None,
+            description: None,
+            status: Some(Status::Done),
+            expected_version: None,
+        })
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        UpdateError::Rejected(PatchError::TicketNotFound(id)) if id == missing
+    ));
+}
+
+#[test]
+fn bulk_updates_are_all_or_nothing() {
+    let client = launch(5);
+    let drafts = (0..3)
+        .map(|_| TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+        .collect();
+    let ids = client.insert_many(drafts).unwrap();
+    assert_eq!(ids.len(), 3);
+
+    let patch = |id, expected_version| TicketPatch {
+        id,
+        title: None,
+        description: None,
+        status: Some(Status::InProgress),
+        expected_version,
+    };
+    // The second patch of the same ticket expects the version bumped by the first one.
+    let batch = vec![
+        patch(ids[0], Some(1)),
+        patch(ids[0], Some(2)),
+        patch(ids[1], Some(2)),
+    ];
+    let err = client.update_many(batch).unwrap_err();
+    let UpdateManyError::Rejected(err) = err else {
+        panic!("unexpected error: {err}");
+    };
+    assert_eq!(
+        err.failures,
+        [(2, PatchError::Conflict(Conflict { current_version: 1 }))]
+    );
+    for &id in &ids {
+        assert_eq!(client.get(id).unwrap().unwrap().version, 1);
+    }
+
+    let batch = ids.iter().map(|&id| patch(id, Some(1))).collect();
+    client.update_many(batch).unwrap();
+    for &id in &ids {
+        let ticket = client.get(id).unwrap().unwrap();
+        assert_eq!(ticket.status, Status::InProgress);
+        assert_eq!(ticket.version, 2);
+    }
+}
+
+#[test]
+fn calls_time_out_instead_of_hanging() {
+    let client = launch(5);
+    let drafts: Vec<TicketDraft> = (0..10_000)
+        .map(|_| TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+        .collect();
+
+    // The server can't insert that many tickets in no time at all.
+    let impatient = client.with_timeout(Duration::ZERO);
+    assert_eq!(impatient.insert_many(drafts), Err(ClientError::Timeout));
+
+    // The server is still available afterwards.
+    let ids = client.insert_many(Vec::new()).unwrap();
+    assert!(ids.is_empty());
+    let last = client
+        .insert(TicketDraft {
+            title: ticket_title(),
+            description: ticket_description(),
+        })
+        .unwrap();
+    assert!(client.get(last).unwrap().is_some());
+}
+
+#[test]
+fn shutdown_drains_the_queue() {
+    let (client, handle) = launch_with_handle(5);
+    let draft = || TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    };
+    let id = client.insert(draft()).unwrap();
+
+    // Queue a batch without waiting for it, so that it's still in flight on shutdown.
+    let drafts = (0..10_000).map(|_| draft()).collect();
+    let in_flight = client.with_timeout(Duration::ZERO).insert_many(drafts);
+    assert_eq!(in_flight, Err(ClientError::Timeout));
+
+    let report = handle.shutdown().unwrap();
+    // The batch was executed, either before or while shutting down.
+    assert_eq!(report.store.len(), 10_001);
+    assert!(report.store.get(id).is_some());
+    assert!(report.drained <= 1);
+    assert_eq!(report.rejected, 0);
+    assert_eq!(client.insert(draft()), Err(ClientError::ServerGone));
+    assert_eq!(client.get(id), Err(ClientError::ServerGone));
+}
@@ -0,0 +1,104 @@
+use patch::data::{Status, TicketDraft, TicketPatch};
+use patch::metrics::CommandKind;
+use patch::{launch, launch_sharded, launch_with, Backpressure};
+use std::time::Duration;
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+fn draft() -> TicketDraft {
+    TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    }
+}
+
+#[test]
+fn counts_commands_by_kind() {
+    let client = launch(5);
+    let id = client.insert(draft()).unwrap();
+    client.insert(draft()).unwrap();
+    client.get(id).unwrap();
+    client
+        .update(TicketPatch {
+            id,
+            title: None,
+            description: None,
+            status: Some(Status::Done),
+            expected_version: None,
+        })
+        .unwrap();
+
+    let metrics = client.stats().unwrap();
+    let count = |kind| metrics.commands.get(&kind).map_or(0, |h| h.count());
+    assert_eq!(count(CommandKind::Insert), 2);
+    assert_eq!(count(CommandKind::Get), 1);
+    assert_eq!(count(CommandKind::Update), 1);
+    assert_eq!(count(CommandKind::List), 0);
+    assert_eq!(metrics.store_size, 2);
+    assert_eq!(metrics.queue_depth, 0);
+
+    // Asking for the metrics doesn't stop the server.
+    client.get(id).unwrap();
+    assert_eq!(
+        client.stats().unwrap().commands[&CommandKind::Get].count(),
+        2
+    );
+}
+
+#[test]
+fn sharded_metrics_are_added_up() {
+    let client = launch_sharded(3, 5);
+    for _ in 0..6 {
+        client.insert(draft()).unwrap();
+    }
+    let metrics = client.stats().unwrap();
+    assert_eq!(metrics.commands[&CommandKind::Insert].count(), 6);
+    assert_eq!(metrics.store_size, 6);
+}
+
+#[test]
+fn queue_depth() {
+    let client = launch_with(5, Backpressure::Prioritized { read_capacity: 1 });
+    let impatient = client.with_timeout(Duration::ZERO);
+    let drafts = (0..50_000).map(|_| draft()).collect();
+    impatient.insert_many(drafts).unwrap_err();
+    impatient.insert(draft()).unwrap_err();
+    impatient.insert(draft()).unwrap_err();
+
+    // The request for metrics skips the queued writes.
+    let metrics = client
+        .with_timeout(Duration::from_secs(30))
+        .stats()
+        .unwrap();
+    assert_eq!(metrics.queue_depth, 2);
+    assert_eq!(metrics.store_size, 50_000);
+}
+
+#[test]
+fn prometheus_export() {
+    let client = launch(5);
+    client.insert(draft()).unwrap();
+    let text = client.stats().unwrap().to_prometheus();
+
+    for line in [
+        "# TYPE ticket_commands_total counter",
+        "ticket_commands_total{command=\"insert\"} 1",
+        "# TYPE ticket_command_duration_seconds histogram",
+        "ticket_command_duration_seconds_bucket{command=\"insert\",le=\"+Inf\"} 1",
+        "ticket_command_duration_seconds_count{command=\"insert\"} 1",
+        "ticket_queue_depth 0",
+        "ticket_store_size 1",
+    ] {
+        assert!(
+            text.lines().any(|l| l == line),
+            "missing `{line}` in:\n{text}"
+        );
+    }
+    // Buckets are cumulative, and end with every command.
+    let buckets: Vec<u64> = text
+        .lines()
+        .filter(|l| l.starts_with("ticket_command_duration_seconds_bucket"))
+        .map(|l| l.rsplit(' ').next().unwrap().parse().unwrap())
+        .collect();
+    assert!(buckets.windows(2).all(|w| w[0] <= w[1]));
+    assert_eq!(buckets.last(), Some(&1));
+}
@@ -0,0 +1,130 @@
+use patch::data::{Conflict, PatchError, Status, TicketDraft, TicketPatch};
+use patch::{launch_sharded, launch_sharded_with_handle, ClientError, UpdateManyError};
+use std::collections::BTreeSet;
+use std::thread::spawn;
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+
+fn draft() -> TicketDraft {
+    TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    }
+}
+
+fn start(id: patch::store::TicketId, expected_version: Option<u64>) -> TicketPatch {
+    TicketPatch {
+        id,
+        title: None,
+        description: None,
+        status: Some(Status::InProgress),
+        expected_version,
+    }
+}
+
+#[test]
+fn ids_are_unique_and_lists_are_merged_in_order() {
+    let client = launch_sharded(4, 10);
+    let handles: Vec<_> = (0..4)
+        .map(|_| {
+            let client = client.clone();
+            spawn(move || {
+                (0..25)
+                    .map(|_| client.insert(draft()).unwrap())
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    let ids: BTreeSet<_> = handles
+        .into_iter()
+        .flat_map(|h| h.join().unwrap())
+        .collect();
+    assert_eq!(ids.len(), 100);
+
+    for &id in &ids {
+        assert_eq!(client.get(id).unwrap().unwrap().id, id);
+    }
+    let listed: Vec<_> = client.list().unwrap().into_iter().map(|t| t.id).collect();
+    assert_eq!(listed, ids.iter().copied().collect::<Vec<_>>());
+
+    let started: Vec<_> = ids.iter().copied().step_by(3).collect();
+    for &id in &started {
+        client.update(start(id, None)).unwrap();
+    }
+    let in_progress: Vec<_> = client
+        .list_by_status(Status::InProgress)
+        .unwrap()
+        .into_iter()
+        .map(|t| t.id)
+        .collect();
+    assert_eq!(in_progress, started);
+}
+
+#[test]
+fn batches_across_shards_are_all_or_nothing() {
+    let client = launch_sharded(3, 10);
+    let ids: Vec<_> = (0..3).map(|_| client.insert(draft()).unwrap()).collect();
+
+    let batch = vec![
+        start(ids[0], Some(1)),
+        start(ids[1], Some(7)),
+        start(ids[2], Some(1)),
+    ];
+    let Err(UpdateManyError::Rejected(rejected)) = client.update_many(batch) else {
+        panic!("the batch should have been rejected");
+    };
+    assert_eq!(
+        rejected.failures,
+        [(1, PatchError::Conflict(Conflict { current_version: 1 }))]
+    );
+    assert!(client
+        .list_by_status(Status::InProgress)
+        .unwrap()
+        .is_empty());
+
+    let batch = ids.iter().map(|&id| start(id, Some(1))).collect();
+    client.update_many(batch).unwrap();
+    assert_eq!(client.list_by_status(Status::InProgress).unwrap().len(), 3);
+}
+
+#[test]
+fn concurrent_batches_do_not_deadlock() {
+    let client = launch_sharded(4, 100);
+    let ids: Vec<_> = (0..8).map(|_| client.insert(draft()).unwrap()).collect();
+
+    let handles: Vec<_> = (0..8)
+        .map(|i| {
+            let client = client.clone();
+            let mut ids = ids.clone();
+            // Each thread lists the tickets in a different order.
+            ids.rotate_left(i);
+            spawn(move || {
+                for _ in 0..20 {
+                    let batch = ids.iter().map(|&id| start(id, None)).collect();
+                    client.update_many(batch).unwrap();
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    for ticket in client.list().unwrap() {
+        assert_eq!(ticket.version, 1 + 8 * 20);
+    }
+}
+
+#[test]
+fn shutdown_merges_every_shard() {
+    let (client, handle) = launch_sharded_with_handle(3, 10);
+    let ids: Vec<_> = (0..7).map(|_| client.insert(draft()).unwrap()).collect();
+
+    let report = handle.shutdown().unwrap();
+    assert_eq!(report.store.len(), 7);
+    assert!(ids.iter().all(|&id| report.store.get(id).is_some()));
+    assert_eq!(client.list(), Err(ClientError::ServerGone));
+
+    // The merged store doesn't reuse the ids of any shard.
+    let mut store = report.store;
+    let id = store.add_ticket(draft());
+    assert!(!ids.contains(&id));
+}
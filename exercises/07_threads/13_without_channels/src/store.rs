@@ -1,7 +1,7 @@
 use std::collections::BTreeMap;
 use std::sync::{Arc, RwLock};
 
-use crate::data::{Status, Ticket, TicketDraft};
+use crate::data::{PatchError, Status, Ticket, TicketDraft, TicketPatch};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct TicketId(u64);
@@ -28,6 +28,7 @@ impl TicketStore {
             title: ticket.title,
             description: ticket.description,
             status: Status::ToDo,
+            version: 1,
         };
         let ticket = Arc::new(RwLock::new(ticket));
         self.tickets.insert(id, ticket);
@@ -37,4 +38,19 @@ impl TicketStore {
     pub fn get(&self, id: TicketId) -> Option<Arc<RwLock<Ticket>>> {
         self.tickets.get(&id).cloned()
     }
+
+    /// Applies `patch` while holding the ticket's write lock, so that the version
+    /// check and the update can't interleave with another writer.
+    pub fn patch(&self, patch: TicketPatch) -> Result<(), PatchError> {
+        match self.tickets.get(&patch.id) {
+            Some(ticket) => Ok(ticket.write().unwrap().apply(patch)?),
+            None => Err(PatchError::TicketNotFound(patch.id)),
+        }
+    }
+}
+
+impl Default for TicketStore {
+    fn default() -> Self {
+        Self::new()
+    }
 }
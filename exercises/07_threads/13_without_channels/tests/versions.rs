@@ -0,0 +1,89 @@
+use std::sync::{Arc, Barrier};
+use std::thread::spawn;
+
+use ticket_fields::test_helpers::{ticket_description, ticket_title};
+use without_channels::data::{Conflict, PatchError, Status, TicketDraft, TicketPatch};
+use without_channels::store::TicketStore;
+
+#[test]
+fn only_one_of_two_concurrent_patches_wins() {
+    let mut store = TicketStore::new();
+    let id = store.add_ticket(TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    });
+    let version = store.get(id).unwrap().read().unwrap().version;
+    let store = Arc::new(store);
+    let barrier = Arc::new(Barrier::new(2));
+
+    let handles: Vec<_> = [Status::InProgress, Status::Done]
+        .into_iter()
+        .map(|status| {
+            let store = Arc::clone(&store);
+            let barrier = Arc::clone(&barrier);
+            spawn(move || {
+                barrier.wait();
+                store.patch(TicketPatch {
+                    id,
+                    title: None,
+                    description: None,
+                    status: Some(status),
+                    expected_version: Some(version),
+                })
+            })
+        })
+        .collect();
+    let results: Vec<_> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+
+    assert_eq!(results.iter().filter(|r| r.is_ok()).count(), 1);
+    assert!(results.contains(&Err(PatchError::Conflict(Conflict { current_version: 2 }))));
+    let ticket = store.get(id).unwrap();
+    assert_eq!(ticket.read().unwrap().version, 2);
+}
+
+#[test]
+fn unversioned_patches_always_apply() {
+    let mut store = TicketStore::new();
+    let id = store.add_ticket(TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    });
+    for status in [Status::InProgress, Status::Done] {
+        store
+            .patch(TicketPatch {
+                id,
+                title: None,
+                description: None,
+                status: Some(status),
+                expected_version: None,
+            })
+            .unwrap();
+    }
+    let ticket = store.get(id).unwrap();
+    let ticket = ticket.read().unwrap();
+    assert_eq!(ticket.status, Status::Done);
+    assert_eq!(ticket.version, 3);
+}
+
+#[test]
+fn patching_a_missing_ticket_is_an_error() {
+    let draft = || TicketDraft {
+        title: ticket_title(),
+        description: ticket_description(),
+    };
+    let mut other = TicketStore::new();
+    other.add_ticket(draft());
+    // An id that `store` never assigned.
+    let missing = other.add_ticket(draft());
+    let mut store = TicketStore::new();
+    store.add_ticket(draft());
+
+    let result = store.patch(TicketPatch {
+        id: missing,
+        title: None,
+        description: None,
+        status: Some(Status::Done),
+        expected_version: None,
+    });
+    assert_eq!(result, Err(PatchError::TicketNotFound(missing)));
+}
@@ -0,0 +1,36 @@
+use crate::FieldPolicy;
+
+crate::bounded_string!(
+    /// The name of the person a ticket is assigned to.
+    pub struct Assignee,
+    AssigneeError,
+    "assignee",
+    FieldPolicy::chars(64)
+        .nfc(true)
+        .trim(true)
+        .collapse_whitespace(true)
+        .reject_control_characters(true)
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalizes_name() {
+        let assignee = Assignee::try_from("  Ada \t Lovelace ").unwrap();
+        assert_eq!(assignee.as_ref(), "Ada Lovelace");
+    }
+
+    #[test]
+    fn test_counts_chars() {
+        let assignee = Assignee::try_from("王小明").unwrap();
+        assert_eq!(assignee.to_string(), "王小明");
+    }
+
+    #[test]
+    fn test_rejects_control_characters() {
+        let err = Assignee::try_from("Ada\u{0}").unwrap_err();
+        assert_eq!(err, AssigneeError::ContainsControlCharacters);
+    }
+}
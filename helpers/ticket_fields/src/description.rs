@@ -1,41 +1,15 @@
-#[derive(Debug, PartialEq, Clone, Eq)]
-pub struct TicketDescription(String);
+use crate::FieldPolicy;
 
-#[derive(Debug, thiserror::Error)]
-pub enum TicketDescriptionError {
-    #[error("The description cannot be empty")]
-    Empty,
-    #[error("The description cannot be longer than 500 bytes")]
-    TooLong,
-}
-
-impl TryFrom<String> for TicketDescription {
-    type Error = TicketDescriptionError;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        validate(&value)?;
-        Ok(Self(value))
-    }
-}
-
-impl TryFrom<&str> for TicketDescription {
-    type Error = TicketDescriptionError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        validate(value)?;
-        Ok(Self(value.to_string()))
-    }
-}
-
-fn validate(description: &str) -> Result<(), TicketDescriptionError> {
-    if description.is_empty() {
-        Err(TicketDescriptionError::Empty)
-    } else if description.len() > 500 {
-        Err(TicketDescriptionError::TooLong)
-    } else {
-        Ok(())
-    }
-}
+crate::bounded_string!(
+    /// A ticket description of at most 500 characters.
+    ///
+    /// The limit counts characters rather than bytes, so the length error now
+    /// reads "cannot be longer than 500 characters" where it used to say "bytes".
+    pub struct TicketDescription,
+    TicketDescriptionError,
+    "description",
+    FieldPolicy::chars(500)
+);
 
 #[cfg(test)]
 mod tests {
@@ -58,10 +32,11 @@ mod tests {
 
     #[test]
     fn test_try_from_long_string() {
+        // The default policy counts characters, hence "characters" rather than "bytes".
         let err = TicketDescription::try_from(overly_long_description()).unwrap_err();
         assert_eq!(
             err.to_string(),
-            "The description cannot be longer than 500 bytes"
+            "The description cannot be longer than 500 characters"
         );
     }
 
@@ -70,4 +45,24 @@ mod tests {
         let description = TicketDescription::try_from("A description").unwrap();
         assert_eq!(description.0, "A description");
     }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let description = TicketDescription::try_from(valid_description()).unwrap();
+        let json = serde_json::to_string(&description).unwrap();
+        assert_eq!(json, serde_json::to_string(&valid_description()).unwrap());
+        let deserialized: TicketDescription = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, description);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_validates() {
+        let json = serde_json::to_string(&overly_long_description()).unwrap();
+        let err = serde_json::from_str::<TicketDescription>(&json).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The description cannot be longer than 500 characters"));
+    }
 }
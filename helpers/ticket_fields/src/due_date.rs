@@ -0,0 +1,195 @@
+use crate::i18n::{render, Locale, Localize};
+use std::fmt;
+use std::str::FromStr;
+
+/// A calendar date, parsed from an ISO-8601 `YYYY-MM-DD` string.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DueDate {
+    year: u16,
+    month: u8,
+    day: u8,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum DueDateError {
+    #[error("`{0}` is not a date in the YYYY-MM-DD format")]
+    InvalidFormat(String),
+    #[error("{month} is not a valid month")]
+    InvalidMonth { month: u8 },
+    #[error("{year:04}-{month:02} does not have a day {day}")]
+    InvalidDay { year: u16, month: u8, day: u8 },
+}
+
+impl Localize for DueDateError {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            DueDateError::InvalidFormat(value) => {
+                render(locale, "due_date.invalid_format", &[("value", value)])
+            }
+            DueDateError::InvalidMonth { month } => render(
+                locale,
+                "due_date.invalid_month",
+                &[("month", &month.to_string())],
+            ),
+            DueDateError::InvalidDay { year, month, day } => render(
+                locale,
+                "due_date.invalid_day",
+                &[
+                    ("year", &format!("{year:04}")),
+                    ("month", &format!("{month:02}")),
+                    ("day", &day.to_string()),
+                ],
+            ),
+        }
+    }
+}
+
+impl DueDate {
+    pub fn new(year: u16, month: u8, day: u8) -> Result<Self, DueDateError> {
+        if !(1..=12).contains(&month) {
+            return Err(DueDateError::InvalidMonth { month });
+        }
+        if day == 0 || day > days_in_month(year, month) {
+            return Err(DueDateError::InvalidDay { year, month, day });
+        }
+        Ok(Self { year, month, day })
+    }
+
+    pub fn year(&self) -> u16 {
+        self.year
+    }
+
+    pub fn month(&self) -> u8 {
+        self.month
+    }
+
+    pub fn day(&self) -> u8 {
+        self.day
+    }
+}
+
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+impl TryFrom<&str> for DueDate {
+    type Error = DueDateError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let invalid_format = || DueDateError::InvalidFormat(value.to_string());
+        let bytes = value.as_bytes();
+        let well_formed = bytes.len() == 10
+            && bytes[4] == b'-'
+            && bytes[7] == b'-'
+            && bytes
+                .iter()
+                .enumerate()
+                .all(|(i, b)| i == 4 || i == 7 || b.is_ascii_digit());
+        if !well_formed {
+            return Err(invalid_format());
+        }
+        let year = value[0..4].parse().map_err(|_| invalid_format())?;
+        let month = value[5..7].parse().map_err(|_| invalid_format())?;
+        let day = value[8..10].parse().map_err(|_| invalid_format())?;
+        DueDate::new(year, month, day)
+    }
+}
+
+impl TryFrom<String> for DueDate {
+    type Error = DueDateError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl FromStr for DueDate {
+    type Err = DueDateError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+impl fmt::Display for DueDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04}-{:02}-{:02}", self.year, self.month, self.day)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for DueDate {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for DueDate {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_str() {
+        let date = DueDate::try_from("2024-02-29").unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2024, 2, 29));
+        assert_eq!(date.to_string(), "2024-02-29");
+    }
+
+    #[test]
+    fn test_invalid_format() {
+        for input in [
+            "2024-2-29",
+            "2024/02/29",
+            "24-02-29",
+            "2024-02-29T10:00",
+            "２０２４-02-29",
+        ] {
+            let err = DueDate::try_from(input).unwrap_err();
+            assert_eq!(err, DueDateError::InvalidFormat(input.to_string()));
+        }
+    }
+
+    #[test]
+    fn test_invalid_date() {
+        assert_eq!(
+            DueDate::try_from("2023-02-29"),
+            Err(DueDateError::InvalidDay {
+                year: 2023,
+                month: 2,
+                day: 29
+            })
+        );
+        assert_eq!(
+            DueDate::try_from("1900-02-29").unwrap_err().to_string(),
+            "1900-02 does not have a day 29"
+        );
+        assert_eq!(
+            DueDate::try_from("2024-13-01"),
+            Err(DueDateError::InvalidMonth { month: 13 })
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        let earlier: DueDate = "2024-12-31".parse().unwrap();
+        let later: DueDate = "2025-01-01".parse().unwrap();
+        assert!(earlier < later);
+    }
+}
@@ -0,0 +1,210 @@
+//! Localized rendering of validation errors.
+//!
+//! Every error type in this crate keeps its English message as its `Display`
+//! implementation. [`Localize`] renders the same message in a chosen [`Locale`],
+//! using the message catalog below. Messages missing from a locale fall back to English.
+
+use crate::{LengthUnit, PolicyViolation};
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Default)]
+pub enum Locale {
+    #[default]
+    En,
+    ZhCn,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{0}` is not a supported locale. Use one of: en, zh-CN")]
+pub struct UnsupportedLocaleError(String);
+
+impl FromStr for Locale {
+    type Err = UnsupportedLocaleError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().replace('_', "-").as_str() {
+            "en" | "en-us" | "en-gb" => Ok(Locale::En),
+            "zh" | "zh-cn" | "zh-hans" | "zh-hans-cn" => Ok(Locale::ZhCn),
+            _ => Err(UnsupportedLocaleError(s.to_string())),
+        }
+    }
+}
+
+/// An error whose message can be rendered in more than one language.
+pub trait Localize {
+    fn localize(&self, locale: Locale) -> String;
+}
+
+const EN: &[(&str, &str)] = &[
+    ("field.empty", "The {field} cannot be empty"),
+    (
+        "field.too_long",
+        "The {field} cannot be longer than {max_length} {unit}",
+    ),
+    (
+        "field.control_characters",
+        "The {field} cannot contain control characters",
+    ),
+    ("unit.bytes", "bytes"),
+    ("unit.chars", "characters"),
+    ("unit.graphemes", "grapheme clusters"),
+    (
+        "priority.invalid",
+        "`{value}` is not a valid priority. Use one of: Low, Medium, High, Critical",
+    ),
+    (
+        "labels.too_many",
+        "A ticket cannot have more than {max} labels",
+    ),
+    (
+        "labels.invalid_label",
+        "Label #{index} is invalid: {source}",
+    ),
+    (
+        "due_date.invalid_format",
+        "`{value}` is not a date in the YYYY-MM-DD format",
+    ),
+    ("due_date.invalid_month", "{month} is not a valid month"),
+    (
+        "due_date.invalid_day",
+        "{year}-{month} does not have a day {day}",
+    ),
+];
+
+const ZH_CN: &[(&str, &str)] = &[
+    ("field.empty", "{field}不能为空"),
+    ("field.too_long", "{field}不能超过 {max_length} {unit}"),
+    ("field.control_characters", "{field}不能包含控制字符"),
+    ("field.title", "标题"),
+    ("field.description", "描述"),
+    ("field.label", "标签"),
+    ("field.assignee", "负责人"),
+    ("unit.bytes", "字节"),
+    ("unit.chars", "个字符"),
+    ("unit.graphemes", "个字素簇"),
+    (
+        "priority.invalid",
+        "`{value}` 不是有效的优先级。可选值：Low、Medium、High、Critical",
+    ),
+    ("labels.too_many", "一个工单最多只能有 {max} 个标签"),
+    ("labels.invalid_label", "第 {index} 个标签无效：{source}"),
+    (
+        "due_date.invalid_format",
+        "`{value}` 不是 YYYY-MM-DD 格式的日期",
+    ),
+    ("due_date.invalid_month", "{month} 不是有效的月份"),
+    ("due_date.invalid_day", "{year}-{month} 没有第 {day} 天"),
+];
+
+fn lookup(locale: Locale, key: &str) -> Option<&'static str> {
+    let catalog = match locale {
+        Locale::En => EN,
+        Locale::ZhCn => ZH_CN,
+    };
+    catalog.iter().find(|(k, _)| *k == key).map(|(_, v)| *v)
+}
+
+/// Renders the message `key` in `locale`, substituting each `{name}` placeholder.
+pub(crate) fn render(locale: Locale, key: &str, args: &[(&str, &str)]) -> String {
+    let template = lookup(locale, key)
+        .or_else(|| lookup(Locale::En, key))
+        .unwrap_or(key);
+    args.iter()
+        .fold(template.to_string(), |message, (name, value)| {
+            message.replace(&format!("{{{name}}}"), value)
+        })
+}
+
+/// Renders a policy violation for the field named `field`.
+///
+/// Field names without a translation are rendered as-is.
+#[doc(hidden)]
+pub fn localize_violation(locale: Locale, field: &str, violation: PolicyViolation) -> String {
+    let field = lookup(locale, &format!("field.{field}")).unwrap_or(field);
+    match violation {
+        PolicyViolation::Empty => render(locale, "field.empty", &[("field", field)]),
+        PolicyViolation::TooLong { max_length, unit } => {
+            let unit_key = match unit {
+                LengthUnit::Bytes => "unit.bytes",
+                LengthUnit::Chars => "unit.chars",
+                LengthUnit::Graphemes => "unit.graphemes",
+            };
+            render(
+                locale,
+                "field.too_long",
+                &[
+                    ("field", field),
+                    ("max_length", &max_length.to_string()),
+                    ("unit", &render(locale, unit_key, &[])),
+                ],
+            )
+        }
+        PolicyViolation::ContainsControlCharacters => {
+            render(locale, "field.control_characters", &[("field", field)])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        DueDate, FieldPolicy, TicketDescription, TicketLabels, TicketPriority, TicketTitle,
+    };
+    use common::{overly_long_description, overly_long_title};
+
+    #[test]
+    fn test_parse_locale() {
+        assert_eq!("en".parse(), Ok(Locale::En));
+        assert_eq!("zh-CN".parse(), Ok(Locale::ZhCn));
+        assert_eq!("zh_cn".parse(), Ok(Locale::ZhCn));
+        assert!("fr".parse::<Locale>().is_err());
+    }
+
+    fn display_and_english<E: std::fmt::Display + Localize>(error: E) -> (String, String) {
+        (error.to_string(), error.localize(Locale::En))
+    }
+
+    #[test]
+    fn test_english_matches_display() {
+        let cases = [
+            display_and_english(TicketTitle::try_from("").unwrap_err()),
+            display_and_english(TicketTitle::try_from(overly_long_title()).unwrap_err()),
+            display_and_english(
+                TicketDescription::try_from(overly_long_description()).unwrap_err(),
+            ),
+            display_and_english(TicketPriority::try_from("urgent").unwrap_err()),
+            display_and_english(TicketLabels::try_from("bug,,ui").unwrap_err()),
+            display_and_english(DueDate::try_from("2023-02-29").unwrap_err()),
+            display_and_english(DueDate::try_from("2023-02").unwrap_err()),
+        ];
+        for (display, english) in cases {
+            assert_eq!(display, english);
+        }
+    }
+
+    #[test]
+    fn test_chinese_messages() {
+        let err = TicketTitle::try_from("").unwrap_err();
+        assert_eq!(err.localize(Locale::ZhCn), "标题不能为空");
+
+        let err = TicketTitle::try_from(overly_long_title()).unwrap_err();
+        assert_eq!(err.localize(Locale::ZhCn), "标题不能超过 50 个字符");
+
+        let policy = FieldPolicy::chars(3);
+        let err = TicketDescription::with_policy("描述太长了", &policy).unwrap_err();
+        assert_eq!(err.localize(Locale::ZhCn), "描述不能超过 3 个字符");
+
+        let err = TicketLabels::try_from("bug,,ui").unwrap_err();
+        assert_eq!(err.localize(Locale::ZhCn), "第 1 个标签无效：标签不能为空");
+
+        let err = DueDate::try_from("2023-02-29").unwrap_err();
+        assert_eq!(err.localize(Locale::ZhCn), "2023-02 没有第 29 天");
+    }
+
+    #[test]
+    fn test_untranslated_field_name() {
+        let message = localize_violation(Locale::ZhCn, "project key", PolicyViolation::Empty);
+        assert_eq!(message, "project key不能为空");
+    }
+}
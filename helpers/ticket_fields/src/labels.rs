@@ -0,0 +1,197 @@
+use crate::i18n::{render, Locale, Localize};
+use crate::FieldPolicy;
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+crate::bounded_string!(
+    pub struct TicketLabel,
+    TicketLabelError,
+    "label",
+    FieldPolicy::chars(30).trim(true).reject_control_characters(true)
+);
+
+/// A deduplicated, ordered set of labels.
+#[derive(Debug, PartialEq, Clone, Eq, Default)]
+pub struct TicketLabels(BTreeSet<TicketLabel>);
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum TicketLabelsError {
+    #[error("A ticket cannot have more than {max} labels")]
+    TooMany { max: usize },
+    #[error("Label #{index} is invalid: {source}")]
+    InvalidLabel {
+        index: usize,
+        source: TicketLabelError,
+    },
+}
+
+impl Localize for TicketLabelsError {
+    fn localize(&self, locale: Locale) -> String {
+        match self {
+            TicketLabelsError::TooMany { max } => {
+                render(locale, "labels.too_many", &[("max", &max.to_string())])
+            }
+            TicketLabelsError::InvalidLabel { index, source } => render(
+                locale,
+                "labels.invalid_label",
+                &[
+                    ("index", &index.to_string()),
+                    ("source", &source.localize(locale)),
+                ],
+            ),
+        }
+    }
+}
+
+impl TicketLabels {
+    /// The maximum number of distinct labels on a ticket.
+    pub const MAX_LABELS: usize = 10;
+
+    pub fn new() -> Self {
+        Self(BTreeSet::new())
+    }
+
+    /// Adds a label, returning `Ok(false)` if it was already present.
+    pub fn insert(&mut self, label: TicketLabel) -> Result<bool, TicketLabelsError> {
+        if self.0.contains(&label) {
+            return Ok(false);
+        }
+        if self.0.len() == Self::MAX_LABELS {
+            return Err(TicketLabelsError::TooMany {
+                max: Self::MAX_LABELS,
+            });
+        }
+        Ok(self.0.insert(label))
+    }
+
+    pub fn remove(&mut self, label: &str) -> bool {
+        let before = self.0.len();
+        self.0.retain(|l| l.as_ref() != label);
+        self.0.len() != before
+    }
+
+    pub fn contains(&self, label: &str) -> bool {
+        self.0.iter().any(|l| l.as_ref() == label)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TicketLabel> {
+        self.0.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl<'a> IntoIterator for &'a TicketLabels {
+    type Item = &'a TicketLabel;
+    type IntoIter = std::collections::btree_set::Iter<'a, TicketLabel>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.0.iter()
+    }
+}
+
+impl TryFrom<Vec<String>> for TicketLabels {
+    type Error = TicketLabelsError;
+
+    fn try_from(value: Vec<String>) -> Result<Self, Self::Error> {
+        let mut labels = TicketLabels::new();
+        for (index, raw) in value.into_iter().enumerate() {
+            let label = TicketLabel::try_from(raw)
+                .map_err(|source| TicketLabelsError::InvalidLabel { index, source })?;
+            labels.insert(label)?;
+        }
+        Ok(labels)
+    }
+}
+
+impl TryFrom<&str> for TicketLabels {
+    type Error = TicketLabelsError;
+
+    /// Parses a comma-separated list of labels, e.g. `"bug, ui"`.
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        if value.trim().is_empty() {
+            return Ok(TicketLabels::new());
+        }
+        value
+            .split(',')
+            .map(str::to_string)
+            .collect::<Vec<_>>()
+            .try_into()
+    }
+}
+
+impl FromStr for TicketLabels {
+    type Err = TicketLabelsError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TicketLabels {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(&self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TicketLabels {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = Vec::<String>::deserialize(deserializer)?;
+        Self::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deduplicates() {
+        let labels = TicketLabels::try_from("bug, ui, bug ,ui").unwrap();
+        let labels: Vec<&str> = labels.iter().map(|l| l.as_ref()).collect();
+        assert_eq!(labels, ["bug", "ui"]);
+    }
+
+    #[test]
+    fn test_invalid_label() {
+        let err = TicketLabels::try_from("bug, ,ui").unwrap_err();
+        assert_eq!(
+            err,
+            TicketLabelsError::InvalidLabel {
+                index: 1,
+                source: TicketLabelError::Empty
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "Label #1 is invalid: The label cannot be empty"
+        );
+    }
+
+    #[test]
+    fn test_too_many() {
+        let raw: Vec<String> = (0..=TicketLabels::MAX_LABELS)
+            .map(|i| format!("label-{i}"))
+            .collect();
+        let err = TicketLabels::try_from(raw).unwrap_err();
+        assert_eq!(err, TicketLabelsError::TooMany { max: 10 });
+    }
+
+    #[test]
+    fn test_insert_and_remove() {
+        let mut labels = TicketLabels::new();
+        assert!(labels.insert("bug".parse().unwrap()).unwrap());
+        assert!(!labels.insert("bug".parse().unwrap()).unwrap());
+        assert!(labels.contains("bug"));
+        assert!(labels.remove("bug"));
+        assert!(labels.is_empty());
+    }
+}
@@ -1,6 +1,30 @@
+mod assignee;
 mod description;
+mod due_date;
+mod i18n;
+mod labels;
+mod macros;
+#[cfg(feature = "markdown")]
+mod markdown;
+mod policy;
+mod priority;
 pub mod test_helpers;
 mod title;
 
-pub use description::TicketDescription;
-pub use title::TicketTitle;
+pub use assignee::{Assignee, AssigneeError};
+pub use description::{TicketDescription, TicketDescriptionError};
+pub use due_date::{DueDate, DueDateError};
+pub use i18n::{Locale, Localize, UnsupportedLocaleError};
+pub use labels::{TicketLabel, TicketLabelError, TicketLabels, TicketLabelsError};
+#[cfg(feature = "markdown")]
+pub use markdown::{Block, ChecklistProgress, DescriptionDocument, Link};
+pub use policy::{FieldPolicy, LengthUnit, PolicyViolation};
+pub use priority::{ParsePriorityError, TicketPriority};
+pub use title::{TicketTitle, TicketTitleError};
+
+#[doc(hidden)]
+pub mod __private {
+    pub use crate::i18n::localize_violation;
+    #[cfg(feature = "serde")]
+    pub use serde;
+}
@@ -0,0 +1,204 @@
+/// Defines a validated string newtype and its error enum.
+///
+/// The generated type implements `TryFrom<String>`, `TryFrom<&str>`,
+/// `FromStr`, `AsRef<str>` and `Display`, and validates its input against the
+/// given [`FieldPolicy`](crate::FieldPolicy), exposed as `DEFAULT_POLICY`.
+/// Its error enum implements [`Localize`](crate::Localize).
+/// With the `serde` feature enabled, it also implements `Serialize` and a
+/// validating `Deserialize`.
+///
+/// The macro generates a `bounded_string_tests` module covering the basic
+/// validation rules, so it can be invoked at most once per module.
+///
+/// ```
+/// use ticket_fields::{bounded_string, FieldPolicy};
+///
+/// bounded_string!(pub struct ProjectKey, ProjectKeyError, "project key", FieldPolicy::chars(10).trim(true));
+///
+/// let key: ProjectKey = " CORE ".parse().unwrap();
+/// assert_eq!(key.as_ref(), "CORE");
+/// let err = ProjectKey::try_from("").unwrap_err();
+/// assert_eq!(err.to_string(), "The project key cannot be empty");
+/// ```
+#[macro_export]
+macro_rules! bounded_string {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident, $error:ident, $field:literal, $policy:expr $(,)?
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, PartialEq, Clone, Eq, PartialOrd, Ord, Hash)]
+        $vis struct $name(String);
+
+        #[doc = concat!("The error returned when a ", $field, " fails validation.")]
+        #[derive(Debug, PartialEq, Clone, Eq)]
+        $vis enum $error {
+            Empty,
+            TooLong {
+                max_length: usize,
+                unit: $crate::LengthUnit,
+            },
+            ContainsControlCharacters,
+        }
+
+        impl ::std::fmt::Display for $error {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                match self {
+                    $error::Empty => write!(f, concat!("The ", $field, " cannot be empty")),
+                    $error::TooLong { max_length, unit } => write!(
+                        f,
+                        concat!("The ", $field, " cannot be longer than {} {}"),
+                        max_length, unit
+                    ),
+                    $error::ContainsControlCharacters => write!(
+                        f,
+                        concat!("The ", $field, " cannot contain control characters")
+                    ),
+                }
+            }
+        }
+
+        impl ::std::error::Error for $error {}
+
+        impl ::std::convert::From<$crate::PolicyViolation> for $error {
+            fn from(violation: $crate::PolicyViolation) -> Self {
+                match violation {
+                    $crate::PolicyViolation::Empty => $error::Empty,
+                    $crate::PolicyViolation::TooLong { max_length, unit } => {
+                        $error::TooLong { max_length, unit }
+                    }
+                    $crate::PolicyViolation::ContainsControlCharacters => {
+                        $error::ContainsControlCharacters
+                    }
+                }
+            }
+        }
+
+        impl $crate::Localize for $error {
+            fn localize(&self, locale: $crate::Locale) -> String {
+                let violation = match self {
+                    $error::Empty => $crate::PolicyViolation::Empty,
+                    $error::TooLong { max_length, unit } => $crate::PolicyViolation::TooLong {
+                        max_length: *max_length,
+                        unit: *unit,
+                    },
+                    $error::ContainsControlCharacters => {
+                        $crate::PolicyViolation::ContainsControlCharacters
+                    }
+                };
+                $crate::__private::localize_violation(locale, $field, violation)
+            }
+        }
+
+        impl $name {
+            /// The policy used by the `TryFrom` and `FromStr` implementations.
+            pub const DEFAULT_POLICY: $crate::FieldPolicy = $policy;
+
+            /// Normalizes and validates `value` according to `policy`.
+            pub fn with_policy(
+                value: impl Into<String>,
+                policy: &$crate::FieldPolicy,
+            ) -> Result<Self, $error> {
+                Ok(Self(policy.apply(value.into())?))
+            }
+        }
+
+        impl ::std::convert::TryFrom<String> for $name {
+            type Error = $error;
+
+            fn try_from(value: String) -> Result<Self, Self::Error> {
+                Self::with_policy(value, &Self::DEFAULT_POLICY)
+            }
+        }
+
+        impl ::std::convert::TryFrom<&str> for $name {
+            type Error = $error;
+
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                Self::with_policy(value, &Self::DEFAULT_POLICY)
+            }
+        }
+
+        impl ::std::str::FromStr for $name {
+            type Err = $error;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                Self::with_policy(s, &Self::DEFAULT_POLICY)
+            }
+        }
+
+        impl ::std::convert::AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                &self.0
+            }
+        }
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {
+                f.write_str(&self.0)
+            }
+        }
+
+        $crate::__bounded_string_serde!($name);
+
+        #[cfg(test)]
+        mod bounded_string_tests {
+            use super::{$error, $name};
+
+            #[test]
+            fn rejects_empty() {
+                assert_eq!($name::try_from(""), Err($error::Empty));
+            }
+
+            #[test]
+            fn enforces_max_length() {
+                let longest = "a".repeat($name::DEFAULT_POLICY.max_length);
+                assert!($name::try_from(longest.as_str()).is_ok());
+
+                let err = $name::try_from(longest + "a").unwrap_err();
+                assert!(matches!(err, $error::TooLong { .. }));
+            }
+
+            #[test]
+            fn round_trips_through_strings() {
+                let value: $name = "a".parse().unwrap();
+                assert_eq!(value.as_ref(), "a");
+                assert_eq!(value.to_string(), "a");
+            }
+        }
+    };
+}
+
+#[cfg(feature = "serde")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bounded_string_serde {
+    ($name:ident) => {
+        impl $crate::__private::serde::Serialize for $name {
+            fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+            where
+                S: $crate::__private::serde::Serializer,
+            {
+                serializer.serialize_str(&self.0)
+            }
+        }
+
+        impl<'de> $crate::__private::serde::Deserialize<'de> for $name {
+            fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+            where
+                D: $crate::__private::serde::Deserializer<'de>,
+            {
+                let value: String =
+                    $crate::__private::serde::Deserialize::deserialize(deserializer)?;
+                Self::try_from(value).map_err($crate::__private::serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+#[cfg(not(feature = "serde"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __bounded_string_serde {
+    ($name:ident) => {};
+}
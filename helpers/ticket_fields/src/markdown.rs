@@ -0,0 +1,262 @@
+//! A structured view of Markdown ticket descriptions.
+
+use crate::TicketDescription;
+use pulldown_cmark::{Event, Options, Parser, Tag, TagEnd};
+use std::fmt;
+
+/// A top-level element of a description, in document order.
+///
+/// Nested list items are flattened into the sequence.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Block {
+    Heading { level: u8, text: String },
+    Paragraph { text: String },
+    ListItem { text: String },
+    ChecklistItem { checked: bool, text: String },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Link {
+    pub text: String,
+    pub url: String,
+}
+
+/// How many checklist items (`- [x]`) are done, out of all checklist items (`- [ ]`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChecklistProgress {
+    pub done: usize,
+    pub total: usize,
+}
+
+impl ChecklistProgress {
+    /// Returns `true` if there is at least one checklist item and all of them are done.
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.done == self.total
+    }
+}
+
+impl fmt::Display for ChecklistProgress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.done, self.total)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DescriptionDocument {
+    blocks: Vec<Block>,
+    links: Vec<Link>,
+}
+
+/// A list item whose text is still being collected.
+struct PendingItem {
+    checked: Option<bool>,
+    emitted: bool,
+}
+
+impl DescriptionDocument {
+    pub fn parse(markdown: &str) -> Self {
+        let mut document = DescriptionDocument::default();
+        let mut text = String::new();
+        let mut items: Vec<PendingItem> = Vec::new();
+        let mut link: Option<Link> = None;
+
+        for event in Parser::new_ext(markdown, Options::ENABLE_TASKLISTS) {
+            match event {
+                Event::Start(Tag::Heading { .. }) => text.clear(),
+                Event::End(TagEnd::Heading(level)) => {
+                    document.blocks.push(Block::Heading {
+                        level: level as u8,
+                        text: take_text(&mut text),
+                    });
+                }
+                Event::Start(Tag::Paragraph) if items.is_empty() => text.clear(),
+                Event::End(TagEnd::Paragraph) if items.is_empty() => {
+                    document.blocks.push(Block::Paragraph {
+                        text: take_text(&mut text),
+                    });
+                }
+                // Paragraphs of a loose list item are joined into the item text.
+                Event::End(TagEnd::Paragraph) => text.push(' '),
+                Event::Start(Tag::Item) => {
+                    text.clear();
+                    items.push(PendingItem {
+                        checked: None,
+                        emitted: false,
+                    });
+                }
+                Event::TaskListMarker(checked) => {
+                    if let Some(item) = items.last_mut() {
+                        item.checked = Some(checked);
+                    }
+                }
+                // A nested list ends the text of the item containing it.
+                Event::Start(Tag::List(_)) => {
+                    if let Some(item) = items.last_mut() {
+                        document.emit_item(item, &mut text);
+                    }
+                }
+                Event::End(TagEnd::Item) => {
+                    if let Some(mut item) = items.pop() {
+                        document.emit_item(&mut item, &mut text);
+                    }
+                }
+                Event::Start(Tag::Link { dest_url, .. }) => {
+                    link = Some(Link {
+                        text: String::new(),
+                        url: dest_url.to_string(),
+                    });
+                }
+                Event::End(TagEnd::Link) => document.links.extend(link.take()),
+                Event::Text(t) | Event::Code(t) => {
+                    text.push_str(&t);
+                    if let Some(link) = &mut link {
+                        link.text.push_str(&t);
+                    }
+                }
+                Event::SoftBreak | Event::HardBreak => text.push(' '),
+                _ => {}
+            }
+        }
+        document
+    }
+
+    fn emit_item(&mut self, item: &mut PendingItem, text: &mut String) {
+        if item.emitted {
+            return;
+        }
+        item.emitted = true;
+        let text = take_text(text);
+        self.blocks.push(match item.checked {
+            Some(checked) => Block::ChecklistItem { checked, text },
+            None => Block::ListItem { text },
+        });
+    }
+
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks
+    }
+
+    pub fn links(&self) -> &[Link] {
+        &self.links
+    }
+
+    pub fn headings(&self) -> impl Iterator<Item = &str> {
+        self.blocks.iter().filter_map(|block| match block {
+            Block::Heading { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+    }
+
+    /// The checklist items, as `(checked, text)` pairs.
+    pub fn checklist(&self) -> impl Iterator<Item = (bool, &str)> {
+        self.blocks.iter().filter_map(|block| match block {
+            Block::ChecklistItem { checked, text } => Some((*checked, text.as_str())),
+            _ => None,
+        })
+    }
+
+    pub fn checklist_progress(&self) -> ChecklistProgress {
+        self.checklist()
+            .fold(ChecklistProgress::default(), |progress, (checked, _)| {
+                ChecklistProgress {
+                    done: progress.done + usize::from(checked),
+                    total: progress.total + 1,
+                }
+            })
+    }
+}
+
+fn take_text(text: &mut String) -> String {
+    let taken = text.trim().to_string();
+    text.clear();
+    taken
+}
+
+impl TicketDescription {
+    /// Parses the description as Markdown.
+    pub fn to_document(&self) -> DescriptionDocument {
+        DescriptionDocument::parse(self.as_ref())
+    }
+
+    pub fn checklist_progress(&self) -> ChecklistProgress {
+        self.to_document().checklist_progress()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DESCRIPTION: &str = "\
+# Login fails
+
+Users see a **blank page** after login, see [the logs](https://logs.example.com/42).
+
+## Subtasks
+
+- [x] Reproduce locally
+- [ ] Fix the redirect
+  - [x] Add a regression test
+- Plain note
+";
+
+    #[test]
+    fn test_parse() {
+        let document = DescriptionDocument::parse(DESCRIPTION);
+        assert_eq!(
+            document.blocks(),
+            [
+                Block::Heading {
+                    level: 1,
+                    text: "Login fails".into()
+                },
+                Block::Paragraph {
+                    text: "Users see a blank page after login, see the logs.".into()
+                },
+                Block::Heading {
+                    level: 2,
+                    text: "Subtasks".into()
+                },
+                Block::ChecklistItem {
+                    checked: true,
+                    text: "Reproduce locally".into()
+                },
+                Block::ChecklistItem {
+                    checked: false,
+                    text: "Fix the redirect".into()
+                },
+                Block::ChecklistItem {
+                    checked: true,
+                    text: "Add a regression test".into()
+                },
+                Block::ListItem {
+                    text: "Plain note".into()
+                },
+            ]
+        );
+        assert_eq!(
+            document.links(),
+            [Link {
+                text: "the logs".into(),
+                url: "https://logs.example.com/42".into()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_checklist_progress() {
+        let description = TicketDescription::try_from(DESCRIPTION).unwrap();
+        let progress = description.checklist_progress();
+        assert_eq!(progress, ChecklistProgress { done: 2, total: 3 });
+        assert_eq!(progress.to_string(), "2/3");
+        assert!(!progress.is_complete());
+    }
+
+    #[test]
+    fn test_no_checklist() {
+        let description = TicketDescription::try_from("A description").unwrap();
+        let progress = description.checklist_progress();
+        assert_eq!(progress.to_string(), "0/0");
+        assert!(!progress.is_complete());
+    }
+}
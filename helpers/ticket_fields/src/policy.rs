@@ -0,0 +1,210 @@
+use std::fmt;
+use unicode_normalization::UnicodeNormalization;
+use unicode_segmentation::UnicodeSegmentation;
+
+/// The unit used to measure the length of a text field.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LengthUnit {
+    /// UTF-8 encoded bytes, i.e. `str::len`.
+    Bytes,
+    /// Unicode scalar values, i.e. `str::chars().count()`.
+    Chars,
+    /// Extended grapheme clusters, i.e. what a user perceives as a single character.
+    Graphemes,
+}
+
+impl LengthUnit {
+    /// Returns the length of `value`, measured in this unit.
+    pub fn measure(self, value: &str) -> usize {
+        match self {
+            LengthUnit::Bytes => value.len(),
+            LengthUnit::Chars => value.chars().count(),
+            LengthUnit::Graphemes => value.graphemes(true).count(),
+        }
+    }
+}
+
+impl fmt::Display for LengthUnit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LengthUnit::Bytes => write!(f, "bytes"),
+            LengthUnit::Chars => write!(f, "characters"),
+            LengthUnit::Graphemes => write!(f, "grapheme clusters"),
+        }
+    }
+}
+
+/// Validation and normalization rules for a text field.
+///
+/// Normalization runs before validation, in this order: Unicode NFC,
+/// trimming, whitespace collapsing. The length limit and the emptiness check
+/// are applied to the normalized value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FieldPolicy {
+    pub max_length: usize,
+    pub unit: LengthUnit,
+    /// Remove leading and trailing whitespace.
+    pub trim: bool,
+    /// Replace every run of whitespace with a single space.
+    pub collapse_whitespace: bool,
+    /// Convert the value to Unicode Normalization Form C.
+    pub nfc: bool,
+    /// Reject values containing control characters.
+    /// Line breaks and tabs are not considered control characters here;
+    /// use `collapse_whitespace` to fold them.
+    pub reject_control_characters: bool,
+}
+
+/// A rule of a [`FieldPolicy`] that a value failed to satisfy.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PolicyViolation {
+    Empty,
+    TooLong { max_length: usize, unit: LengthUnit },
+    ContainsControlCharacters,
+}
+
+impl FieldPolicy {
+    /// A policy that counts bytes and leaves the value untouched.
+    pub const fn bytes(max_length: usize) -> Self {
+        Self::new(max_length, LengthUnit::Bytes)
+    }
+
+    /// A policy that counts Unicode scalar values and leaves the value untouched.
+    pub const fn chars(max_length: usize) -> Self {
+        Self::new(max_length, LengthUnit::Chars)
+    }
+
+    /// A policy that counts extended grapheme clusters and leaves the value untouched.
+    pub const fn graphemes(max_length: usize) -> Self {
+        Self::new(max_length, LengthUnit::Graphemes)
+    }
+
+    const fn new(max_length: usize, unit: LengthUnit) -> Self {
+        Self {
+            max_length,
+            unit,
+            trim: false,
+            collapse_whitespace: false,
+            nfc: false,
+            reject_control_characters: false,
+        }
+    }
+
+    pub const fn trim(mut self, trim: bool) -> Self {
+        self.trim = trim;
+        self
+    }
+
+    pub const fn collapse_whitespace(mut self, collapse_whitespace: bool) -> Self {
+        self.collapse_whitespace = collapse_whitespace;
+        self
+    }
+
+    pub const fn nfc(mut self, nfc: bool) -> Self {
+        self.nfc = nfc;
+        self
+    }
+
+    pub const fn reject_control_characters(mut self, reject_control_characters: bool) -> Self {
+        self.reject_control_characters = reject_control_characters;
+        self
+    }
+
+    /// Normalizes `value` and checks it against the policy,
+    /// returning the normalized value.
+    pub fn apply(&self, value: String) -> Result<String, PolicyViolation> {
+        let value = self.normalize(value);
+        if self.reject_control_characters && value.chars().any(is_rejected_control) {
+            Err(PolicyViolation::ContainsControlCharacters)
+        } else if value.is_empty() {
+            Err(PolicyViolation::Empty)
+        } else if self.unit.measure(&value) > self.max_length {
+            Err(PolicyViolation::TooLong {
+                max_length: self.max_length,
+                unit: self.unit,
+            })
+        } else {
+            Ok(value)
+        }
+    }
+
+    fn normalize(&self, mut value: String) -> String {
+        if self.nfc {
+            value = value.nfc().collect();
+        }
+        if self.trim && value.trim().len() != value.len() {
+            value = value.trim().to_string();
+        }
+        if self.collapse_whitespace {
+            value = collapse_whitespace(&value);
+        }
+        value
+    }
+}
+
+pub(crate) fn is_rejected_control(c: char) -> bool {
+    c.is_control() && !matches!(c, '\n' | '\r' | '\t')
+}
+
+fn collapse_whitespace(value: &str) -> String {
+    let mut collapsed = String::with_capacity(value.len());
+    let mut previous_was_whitespace = false;
+    for c in value.chars() {
+        if c.is_whitespace() {
+            if !previous_was_whitespace {
+                collapsed.push(' ');
+            }
+            previous_was_whitespace = true;
+        } else {
+            collapsed.push(c);
+            previous_was_whitespace = false;
+        }
+    }
+    collapsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_measure() {
+        // "e" followed by a combining acute accent, then a family emoji.
+        let value = "e\u{301}👨‍👩‍👧";
+        assert_eq!(LengthUnit::Bytes.measure(value), 21);
+        assert_eq!(LengthUnit::Chars.measure(value), 7);
+        assert_eq!(LengthUnit::Graphemes.measure(value), 2);
+    }
+
+    #[test]
+    fn test_normalization() {
+        let policy = FieldPolicy::chars(50)
+            .trim(true)
+            .collapse_whitespace(true)
+            .nfc(true);
+        let value = policy.apply("  Cafe\u{301} \t\n menu  ".into()).unwrap();
+        assert_eq!(value, "Caf\u{e9} menu");
+    }
+
+    #[test]
+    fn test_no_normalization_by_default() {
+        let policy = FieldPolicy::bytes(50);
+        assert_eq!(policy.apply("  a  b ".into()).unwrap(), "  a  b ");
+    }
+
+    #[test]
+    fn test_trimmed_to_empty() {
+        let policy = FieldPolicy::bytes(50).trim(true);
+        assert_eq!(policy.apply(" \t ".into()), Err(PolicyViolation::Empty));
+    }
+
+    #[test]
+    fn test_control_characters() {
+        let policy = FieldPolicy::bytes(50).reject_control_characters(true);
+        assert_eq!(
+            policy.apply("a\u{7}b".into()),
+            Err(PolicyViolation::ContainsControlCharacters)
+        );
+        assert!(policy.apply("a\nb".into()).is_ok());
+    }
+}
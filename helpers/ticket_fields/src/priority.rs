@@ -0,0 +1,127 @@
+use crate::i18n::{render, Locale, Localize};
+use std::fmt;
+use std::str::FromStr;
+
+/// How urgent a ticket is, from least to most urgent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum TicketPriority {
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+#[error("`{invalid_priority}` is not a valid priority. Use one of: Low, Medium, High, Critical")]
+pub struct ParsePriorityError {
+    invalid_priority: String,
+}
+
+impl Localize for ParsePriorityError {
+    fn localize(&self, locale: Locale) -> String {
+        render(
+            locale,
+            "priority.invalid",
+            &[("value", &self.invalid_priority)],
+        )
+    }
+}
+
+impl TryFrom<String> for TicketPriority {
+    type Error = ParsePriorityError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.as_str().try_into()
+    }
+}
+
+impl TryFrom<&str> for TicketPriority {
+    type Error = ParsePriorityError;
+
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        match value.to_lowercase().as_str() {
+            "low" => Ok(TicketPriority::Low),
+            "medium" => Ok(TicketPriority::Medium),
+            "high" => Ok(TicketPriority::High),
+            "critical" => Ok(TicketPriority::Critical),
+            _ => Err(ParsePriorityError {
+                invalid_priority: value.to_string(),
+            }),
+        }
+    }
+}
+
+impl FromStr for TicketPriority {
+    type Err = ParsePriorityError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.try_into()
+    }
+}
+
+impl fmt::Display for TicketPriority {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            TicketPriority::Low => "Low",
+            TicketPriority::Medium => "Medium",
+            TicketPriority::High => "High",
+            TicketPriority::Critical => "Critical",
+        };
+        f.write_str(name)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl serde::Serialize for TicketPriority {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for TicketPriority {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let value = String::deserialize(deserializer)?;
+        Self::try_from(value).map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_try_from_str() {
+        assert_eq!(TicketPriority::try_from("low"), Ok(TicketPriority::Low));
+        assert_eq!(
+            TicketPriority::try_from("MEDIUM"),
+            Ok(TicketPriority::Medium)
+        );
+        assert_eq!(TicketPriority::try_from("High"), Ok(TicketPriority::High));
+        assert_eq!(
+            TicketPriority::try_from("critical".to_string()),
+            Ok(TicketPriority::Critical)
+        );
+    }
+
+    #[test]
+    fn test_try_from_invalid() {
+        let err = TicketPriority::try_from("Urgent").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "`Urgent` is not a valid priority. Use one of: Low, Medium, High, Critical"
+        );
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(TicketPriority::Low < TicketPriority::Medium);
+        assert!(TicketPriority::High < TicketPriority::Critical);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let priority: TicketPriority = TicketPriority::High.to_string().parse().unwrap();
+        assert_eq!(priority, TicketPriority::High);
+    }
+}
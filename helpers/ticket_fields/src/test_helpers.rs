@@ -1,6 +1,9 @@
-use crate::{TicketDescription, TicketTitle};
+use crate::{Assignee, DueDate, TicketDescription, TicketLabels, TicketPriority, TicketTitle};
 use common::{valid_description, valid_title};
 
+#[cfg(feature = "proptest")]
+pub mod strategies;
+
 /// A function to generate a valid ticket title,
 /// for test purposes.
 pub fn ticket_title() -> TicketTitle {
@@ -12,3 +15,27 @@ pub fn ticket_title() -> TicketTitle {
 pub fn ticket_description() -> TicketDescription {
     valid_description().try_into().unwrap()
 }
+
+/// A function to generate a ticket priority,
+/// for test purposes.
+pub fn ticket_priority() -> TicketPriority {
+    TicketPriority::Medium
+}
+
+/// A function to generate a valid set of ticket labels,
+/// for test purposes.
+pub fn ticket_labels() -> TicketLabels {
+    "bug, backend".try_into().unwrap()
+}
+
+/// A function to generate a valid assignee,
+/// for test purposes.
+pub fn assignee() -> Assignee {
+    "Ferris".try_into().unwrap()
+}
+
+/// A function to generate a valid due date,
+/// for test purposes.
+pub fn due_date() -> DueDate {
+    "2030-01-31".try_into().unwrap()
+}
@@ -0,0 +1,189 @@
+//! `proptest` strategies for ticket fields.
+//!
+//! All strategies target the `DEFAULT_POLICY` of each field, i.e. the rules
+//! enforced by its `TryFrom` implementations.
+
+use crate::{
+    policy::is_rejected_control, FieldPolicy, LengthUnit, PolicyViolation, TicketDescription,
+    TicketDescriptionError, TicketTitle, TicketTitleError,
+};
+use proptest::prelude::*;
+
+/// Strings accepted as a ticket title.
+pub fn valid_title() -> impl Strategy<Value = String> {
+    within_limit(TicketTitle::DEFAULT_POLICY)
+}
+
+/// Strings that are exactly one unit or exactly `max_length` units long.
+pub fn boundary_title() -> impl Strategy<Value = String> {
+    at_boundary(TicketTitle::DEFAULT_POLICY)
+}
+
+/// Strings rejected as a ticket title, paired with the expected error.
+pub fn invalid_title() -> impl Strategy<Value = (String, TicketTitleError)> {
+    invalid(TicketTitle::DEFAULT_POLICY).prop_map(|(value, error)| (value, error.into()))
+}
+
+/// Valid ticket titles.
+pub fn ticket_title() -> impl Strategy<Value = TicketTitle> {
+    valid_title().prop_map(|value| TicketTitle::try_from(value).unwrap())
+}
+
+/// Strings accepted as a ticket description.
+pub fn valid_description() -> impl Strategy<Value = String> {
+    within_limit(TicketDescription::DEFAULT_POLICY)
+}
+
+/// Strings that are exactly one unit or exactly `max_length` units long.
+pub fn boundary_description() -> impl Strategy<Value = String> {
+    at_boundary(TicketDescription::DEFAULT_POLICY)
+}
+
+/// Strings rejected as a ticket description, paired with the expected error.
+pub fn invalid_description() -> impl Strategy<Value = (String, TicketDescriptionError)> {
+    invalid(TicketDescription::DEFAULT_POLICY).prop_map(|(value, error)| (value, error.into()))
+}
+
+/// Valid ticket descriptions.
+pub fn ticket_description() -> impl Strategy<Value = TicketDescription> {
+    valid_description().prop_map(|value| TicketDescription::try_from(value).unwrap())
+}
+
+/// Non-empty strings that fit within `policy`'s length limit.
+pub fn within_limit(policy: FieldPolicy) -> impl Strategy<Value = String> {
+    let reject_control_characters = policy.reject_control_characters;
+    let max_length = policy.max_length;
+    let unit = policy.unit;
+    // Every char fits on its own, so trimming to the limit never empties the value.
+    let char = any::<char>().prop_filter("must be allowed by the policy", move |&c| {
+        unit.measure(&c.to_string()) <= max_length
+            && !(reject_control_characters && is_rejected_control(c))
+    });
+    prop::collection::vec(char, 1..=max_length)
+        .prop_map(move |chars| {
+            let mut value: String = chars.into_iter().collect();
+            while unit.measure(&value) > max_length {
+                value.pop();
+            }
+            value
+        })
+        .prop_filter("must be accepted by the policy", move |value| {
+            policy.apply(value.clone()).is_ok()
+        })
+}
+
+/// Strings that are exactly one unit or exactly `policy.max_length` units long.
+pub fn at_boundary(policy: FieldPolicy) -> impl Strategy<Value = String> {
+    let reject_control_characters = policy.reject_control_characters;
+    prop_oneof![
+        any::<char>()
+            .prop_filter("must be a single unit", move |c| {
+                policy.unit.measure(&c.to_string()) == 1
+                    && !(reject_control_characters && is_rejected_control(*c))
+            })
+            .prop_map(String::from),
+        within_limit(policy.clone()).prop_map(move |value| pad(value, &policy)),
+    ]
+}
+
+/// Pads `value` with ASCII letters until it is exactly `max_length` units long.
+fn pad(mut value: String, policy: &FieldPolicy) -> String {
+    // A combining mark or a joiner at the end would merge with the padding.
+    if policy.unit == LengthUnit::Graphemes {
+        value.clear();
+    }
+    while policy.unit.measure(&value) < policy.max_length {
+        value.push('a');
+    }
+    value
+}
+
+/// Strings rejected by `policy`, paired with the violated rule.
+pub fn invalid(policy: FieldPolicy) -> impl Strategy<Value = (String, PolicyViolation)> {
+    let too_long = PolicyViolation::TooLong {
+        max_length: policy.max_length,
+        unit: policy.unit,
+    };
+    let padding = policy.clone();
+    let mut cases = vec![
+        Just((String::new(), PolicyViolation::Empty)).boxed(),
+        at_boundary(policy.clone())
+            .prop_map(move |value| {
+                let mut value = pad(value, &padding);
+                value.push('a');
+                (value, too_long)
+            })
+            .boxed(),
+    ];
+    if policy.reject_control_characters {
+        let control = prop_oneof![
+            prop::char::range('\u{0}', '\u{1f}'),
+            prop::char::range('\u{7f}', '\u{9f}'),
+        ]
+        .prop_filter("must not be a line break or a tab", |&c| {
+            is_rejected_control(c)
+        });
+        let with_control = (within_limit(policy), control, any::<prop::sample::Index>()).prop_map(
+            |(mut value, control, at)| {
+                let boundaries: Vec<usize> = value
+                    .char_indices()
+                    .map(|(i, _)| i)
+                    .chain([value.len()])
+                    .collect();
+                value.insert(*at.get(&boundaries), control);
+                (value, PolicyViolation::ContainsControlCharacters)
+            },
+        );
+        cases.push(with_control.boxed());
+    }
+    prop::strategy::Union::new(cases)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    proptest! {
+        #[test]
+        fn valid_titles_are_accepted(value in valid_title()) {
+            prop_assert!(TicketTitle::try_from(value).is_ok());
+        }
+
+        #[test]
+        fn boundary_titles_are_accepted(value in boundary_title()) {
+            let policy = TicketTitle::DEFAULT_POLICY;
+            let length = policy.unit.measure(&value);
+            prop_assert!(length == policy.max_length || length == 1);
+            prop_assert!(TicketTitle::try_from(value).is_ok());
+        }
+
+        #[test]
+        fn invalid_titles_are_rejected((value, expected) in invalid_title()) {
+            prop_assert_eq!(TicketTitle::try_from(value), Err(expected));
+        }
+
+        #[test]
+        fn valid_descriptions_are_accepted(value in valid_description()) {
+            prop_assert!(TicketDescription::try_from(value).is_ok());
+        }
+
+        #[test]
+        fn invalid_descriptions_are_rejected((value, expected) in invalid_description()) {
+            prop_assert_eq!(TicketDescription::try_from(value), Err(expected));
+        }
+
+        #[test]
+        fn small_byte_limits_yield_accepted_values(value in within_limit(FieldPolicy::bytes(2))) {
+            prop_assert!(!value.is_empty());
+            prop_assert!(FieldPolicy::bytes(2).apply(value).is_ok());
+        }
+
+        #[test]
+        fn control_characters_are_rejected(
+            (value, expected) in invalid(FieldPolicy::chars(10).reject_control_characters(true))
+        ) {
+            let policy = FieldPolicy::chars(10).reject_control_characters(true);
+            prop_assert_eq!(policy.apply(value), Err(expected));
+        }
+    }
+}
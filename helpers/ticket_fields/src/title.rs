@@ -1,43 +1,15 @@
-use std::convert::TryFrom;
+use crate::FieldPolicy;
 
-#[derive(Debug, PartialEq, Clone, Eq)]
-pub struct TicketTitle(String);
-
-#[derive(Debug, thiserror::Error)]
-pub enum TicketTitleError {
-    #[error("The title cannot be empty")]
-    Empty,
-    #[error("The title cannot be longer than 50 bytes")]
-    TooLong,
-}
-
-impl TryFrom<String> for TicketTitle {
-    type Error = TicketTitleError;
-
-    fn try_from(value: String) -> Result<Self, Self::Error> {
-        validate(&value)?;
-        Ok(Self(value))
-    }
-}
-
-impl TryFrom<&str> for TicketTitle {
-    type Error = TicketTitleError;
-
-    fn try_from(value: &str) -> Result<Self, Self::Error> {
-        validate(value)?;
-        Ok(Self(value.to_string()))
-    }
-}
-
-fn validate(title: &str) -> Result<(), TicketTitleError> {
-    if title.is_empty() {
-        Err(TicketTitleError::Empty)
-    } else if title.len() > 50 {
-        Err(TicketTitleError::TooLong)
-    } else {
-        Ok(())
-    }
-}
+crate::bounded_string!(
+    /// A ticket title of at most 50 characters.
+    ///
+    /// The limit counts characters rather than bytes, so the length error now
+    /// reads "cannot be longer than 50 characters" where it used to say "bytes".
+    pub struct TicketTitle,
+    TicketTitleError,
+    "title",
+    FieldPolicy::chars(50)
+);
 
 #[cfg(test)]
 mod tests {
@@ -60,8 +32,12 @@ mod tests {
 
     #[test]
     fn test_try_from_long_string() {
+        // The default policy counts characters, hence "characters" rather than "bytes".
         let err = TicketTitle::try_from(overly_long_title()).unwrap_err();
-        assert_eq!(err.to_string(), "The title cannot be longer than 50 bytes");
+        assert_eq!(
+            err.to_string(),
+            "The title cannot be longer than 50 characters"
+        );
     }
 
     #[test]
@@ -69,4 +45,65 @@ mod tests {
         let title = TicketTitle::try_from("A title").unwrap();
         assert_eq!(title.0, "A title");
     }
+
+    #[test]
+    fn test_default_policy_counts_chars() {
+        let input = "登录页面在弹出验证码之后偶尔会无响应并且需要刷新页面";
+        let title = TicketTitle::try_from(input).unwrap();
+        assert_eq!(title.0, input);
+
+        let policy = FieldPolicy::bytes(50);
+        assert!(TicketTitle::with_policy(input, &policy).is_err());
+    }
+
+    #[test]
+    fn test_policy_too_long_message() {
+        let policy = FieldPolicy::graphemes(3);
+        let err = TicketTitle::with_policy("abcd", &policy).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The title cannot be longer than 3 grapheme clusters"
+        );
+    }
+
+    #[test]
+    fn test_policy_normalizes() {
+        let policy = FieldPolicy::chars(50).trim(true).collapse_whitespace(true);
+        let title = TicketTitle::with_policy("  A   title ", &policy).unwrap();
+        assert_eq!(title.0, "A title");
+
+        let err = TicketTitle::with_policy("   ", &policy).unwrap_err();
+        assert_eq!(err, TicketTitleError::Empty);
+    }
+
+    #[test]
+    fn test_policy_rejects_control_characters() {
+        let policy = FieldPolicy::chars(50).reject_control_characters(true);
+        let err = TicketTitle::with_policy("A\u{1b}[31m title", &policy).unwrap_err();
+        assert_eq!(err, TicketTitleError::ContainsControlCharacters);
+        assert_eq!(
+            err.to_string(),
+            "The title cannot contain control characters"
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_serde_round_trip() {
+        let title = TicketTitle::try_from(valid_title()).unwrap();
+        let json = serde_json::to_string(&title).unwrap();
+        assert_eq!(json, serde_json::to_string(&valid_title()).unwrap());
+        let deserialized: TicketTitle = serde_json::from_str(&json).unwrap();
+        assert_eq!(deserialized, title);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn test_deserialize_validates() {
+        let json = serde_json::to_string(&overly_long_title()).unwrap();
+        let err = serde_json::from_str::<TicketTitle>(&json).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("The title cannot be longer than 50 characters"));
+    }
 }
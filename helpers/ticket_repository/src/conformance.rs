@@ -0,0 +1,82 @@
+//! The checks behind [`conformance_tests!`](crate::conformance_tests).
+
+use crate::TicketRepository;
+use std::collections::HashSet;
+use std::fmt::Debug;
+
+pub fn add_and_get<R>(new: fn() -> R, draft: fn() -> R::Draft)
+where
+    R: TicketRepository,
+{
+    let mut repository = new();
+    assert!(repository.is_empty());
+
+    let id = repository.add_ticket(draft());
+    assert!(repository.get(id).is_some());
+    assert!(std::ptr::eq(&repository[id], repository.get(id).unwrap()));
+    assert_eq!(repository.len(), 1);
+    assert!(!repository.is_empty());
+}
+
+pub fn ids_are_unique<R>(new: fn() -> R, draft: fn() -> R::Draft)
+where
+    R: TicketRepository,
+{
+    let mut repository = new();
+    let ids: HashSet<R::Id> = (0..10).map(|_| repository.add_ticket(draft())).collect();
+    assert_eq!(ids.len(), 10);
+    assert_eq!(repository.len(), 10);
+}
+
+pub fn remove<R>(new: fn() -> R, draft: fn() -> R::Draft)
+where
+    R: TicketRepository,
+    R::Ticket: Clone + PartialEq + Debug,
+{
+    let mut repository = new();
+    let kept = repository.add_ticket(draft());
+    let removed = repository.add_ticket(draft());
+    let ticket = repository.get(removed).unwrap().clone();
+
+    assert_eq!(repository.remove(removed), Some(ticket));
+    assert!(repository.get(removed).is_none());
+    assert!(repository.get_mut(removed).is_none());
+    assert!(repository.remove(removed).is_none());
+    assert!(repository.get(kept).is_some());
+    assert_eq!(repository.len(), 1);
+}
+
+pub fn iterate<R>(new: fn() -> R, draft: fn() -> R::Draft)
+where
+    R: TicketRepository,
+    R::Ticket: PartialEq + Debug,
+{
+    let mut repository = new();
+    let ids: Vec<R::Id> = (0..5).map(|_| repository.add_ticket(draft())).collect();
+    let iterated: Vec<&R::Ticket> = repository.iter().collect();
+    assert_eq!(iterated.len(), ids.len());
+    for id in ids {
+        assert!(iterated.contains(&repository.get(id).unwrap()));
+    }
+}
+
+pub fn mutate<R>(new: fn() -> R, draft: fn() -> R::Draft, edit: fn(&mut R::Ticket))
+where
+    R: TicketRepository,
+    R::Ticket: Clone + PartialEq + Debug,
+{
+    let mut repository = new();
+    let first = repository.add_ticket(draft());
+    let second = repository.add_ticket(draft());
+    let before = repository[second].clone();
+
+    let ticket = repository.get_mut(first).unwrap();
+    let original = ticket.clone();
+    edit(ticket);
+    let edited = repository[first].clone();
+    assert_ne!(edited, original);
+
+    edit(&mut repository[second]);
+    assert_ne!(repository[second], before);
+    assert_eq!(repository.get(first), Some(&edited));
+}
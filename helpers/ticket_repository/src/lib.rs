@@ -0,0 +1,87 @@
+//! A storage interface shared by the ticket stores of the course.
+//!
+//! Every exercise defines its own `Ticket`, `TicketDraft` and `TicketId` types,
+//! so the trait is generic over them.
+
+use std::fmt::Debug;
+use std::hash::Hash;
+use std::ops::IndexMut;
+
+#[doc(hidden)]
+pub mod conformance;
+
+/// The operations shared by every ticket store backend.
+///
+/// Code written against this trait can switch between backends, e.g. a
+/// `HashMap`-backed store and a `BTreeMap`-backed one.
+///
+/// Tickets can be read and edited by id through `Index` and `IndexMut`,
+/// which panic if there is no ticket with that id.
+pub trait TicketRepository:
+    IndexMut<<Self as TicketRepository>::Id, Output = <Self as TicketRepository>::Ticket>
+{
+    type Id: Copy + Eq + Hash + Debug;
+    type Ticket;
+    type Draft;
+    type Iter<'a>: Iterator<Item = &'a Self::Ticket>
+    where
+        Self: 'a;
+
+    fn add_ticket(&mut self, draft: Self::Draft) -> Self::Id;
+
+    fn get(&self, id: Self::Id) -> Option<&Self::Ticket>;
+
+    fn get_mut(&mut self, id: Self::Id) -> Option<&mut Self::Ticket>;
+
+    fn remove(&mut self, id: Self::Id) -> Option<Self::Ticket>;
+
+    /// Iterates over all tickets. The order depends on the backend.
+    fn iter(&self) -> Self::Iter<'_>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Generates the conformance suite that every [`TicketRepository`] implementation
+/// must pass, as a set of `#[test]` functions.
+///
+/// `$new` creates an empty repository, `$draft` a valid draft, and `$edit` changes
+/// a ticket in place, e.g. by giving it a title that no draft has.
+///
+/// ```ignore
+/// mod conformance {
+///     ticket_repository::conformance_tests!(crate::TicketStore::new, super::draft, super::edit);
+/// }
+/// ```
+#[macro_export]
+macro_rules! conformance_tests {
+    ($new:expr, $draft:expr, $edit:expr) => {
+        #[test]
+        fn add_and_get() {
+            $crate::conformance::add_and_get($new, $draft);
+        }
+
+        #[test]
+        fn ids_are_unique() {
+            $crate::conformance::ids_are_unique($new, $draft);
+        }
+
+        #[test]
+        fn remove() {
+            $crate::conformance::remove($new, $draft);
+        }
+
+        #[test]
+        fn iterate() {
+            $crate::conformance::iterate($new, $draft);
+        }
+
+        #[test]
+        fn mutate() {
+            $crate::conformance::mutate($new, $draft, $edit);
+        }
+    };
+}